@@ -6,6 +6,7 @@ use connectorx::{
     sources::sqlite::{SqliteSource, SqliteTypeSystem},
     typesystem::TypeConversion,
 };
+use serde_json::{to_string, Value};
 
 pub struct SqlitePandasTransport<'py>(&'py ());
 
@@ -24,6 +25,7 @@ impl_transport!(
         { Time[NaiveTime]            => String[String]          | conversion half }
         { Timestamp[NaiveDateTime]   => DateTime[DateTime<Utc>] | conversion half }
         { Blob[Vec<u8>]              => Bytes[Vec<u8>]          | conversion all }
+        { JSON[Value]                => String[String]          | conversion half }
     }
 );
 
@@ -44,3 +46,9 @@ impl<'py> TypeConversion<NaiveTime, String> for SqlitePandasTransport<'py> {
         val.to_string()
     }
 }
+
+impl<'py> TypeConversion<Value, String> for SqlitePandasTransport<'py> {
+    fn convert(val: Value) -> String {
+        to_string(&val).unwrap()
+    }
+}
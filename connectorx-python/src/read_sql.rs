@@ -52,35 +52,18 @@ pub fn read_sql<'a>(
                 num,
             }),
         ) => {
-            let mut queries = vec![];
-            let num = num as i64;
-
-            let (min, max) = match (min, max) {
-                (None, None) => source_conn
-                    .ty
-                    .get_col_range(conn, &query, &col)
-                    .map_err(ConnectorAgentPythonError::ConnectorAgentError)?,
-                (Some(min), Some(max)) => (min, max),
+            let range = match (min, max) {
+                (None, None) => None,
+                (Some(min), Some(max)) => Some((min, max)),
                 _ => throw!(PyValueError::new_err(
                     "partition_query range can not be partially specified",
                 )),
             };
 
-            let partition_size = (max - min + 1) / num;
-
-            for i in 0..num {
-                let lower = min + i * partition_size;
-                let upper = match i == num - 1 {
-                    true => max + 1,
-                    false => min + (i + 1) * partition_size,
-                };
-                let partition_query = source_conn
-                    .ty
-                    .get_part_query(&query, &col, lower, upper)
-                    .map_err(ConnectorAgentPythonError::ConnectorAgentError)?;
-                queries.push(partition_query);
-            }
-            queries
+            source_conn
+                .ty
+                .get_part_queries(conn, &query, &col, range, num)
+                .map_err(ConnectorAgentPythonError::ConnectorAgentError)?
         }
         (Some(_), Some(_)) => throw!(PyValueError::new_err(
             "partition_query and queries cannot be both specified",
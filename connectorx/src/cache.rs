@@ -0,0 +1,140 @@
+use crate::errors::Result;
+use arrow::record_batch::RecordBatch;
+use log::debug;
+use sqlparser::dialect::GenericDialect;
+use sqlparser::parser::Parser;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+struct Entry {
+    batches: Vec<RecordBatch>,
+    size: usize,
+    created: Instant,
+    last_used: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Key {
+    conn: String,
+    queries: Vec<String>,
+    params: Vec<String>,
+}
+
+/// An opt-in LRU cache of query results, for small queries issued repeatedly (e.g. by dashboards).
+/// Entries are keyed by the connection, the normalized queries and the parameters of the read,
+/// expire after `ttl`, and the least recently used ones are evicted once the arrays exceed
+/// `capacity` bytes.
+pub struct QueryCache {
+    capacity: usize,
+    ttl: Duration,
+    size: usize,
+    tick: u64,
+    entries: HashMap<Key, Entry>,
+}
+
+impl QueryCache {
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        QueryCache {
+            capacity,
+            ttl,
+            size: 0,
+            tick: 0,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Return the cached result of reading `queries` on `conn`, or run `read` and cache what it
+    /// returns. `params` are the other options of the read that change its result (e.g. the
+    /// protocol), reads only share an entry when they are equal.
+    pub fn get_or_read<Q, F>(
+        &mut self,
+        conn: &str,
+        queries: &[Q],
+        params: &[&str],
+        read: F,
+    ) -> Result<Vec<RecordBatch>>
+    where
+        Q: AsRef<str>,
+        F: FnOnce() -> Result<Vec<RecordBatch>>,
+    {
+        let key = Key {
+            conn: conn.to_string(),
+            queries: queries
+                .iter()
+                .map(|q| normalize_query(q.as_ref()))
+                .collect(),
+            params: params.iter().map(|p| p.to_string()).collect(),
+        };
+        self.tick += 1;
+
+        let expired = match self.entries.get_mut(&key) {
+            Some(entry) if entry.created.elapsed() < self.ttl => {
+                debug!("Query cache hit: {:?}", key.queries);
+                entry.last_used = self.tick;
+                return Ok(entry.batches.clone());
+            }
+            Some(_) => true,
+            None => false,
+        };
+        if expired {
+            self.remove(&key);
+        }
+
+        let batches = read()?;
+        let size = batches
+            .iter()
+            .flat_map(|b| b.columns())
+            .map(|c| c.get_array_memory_size())
+            .sum();
+        if size > self.capacity {
+            debug!("Query result of {} bytes is too large to cache", size);
+            return Ok(batches);
+        }
+
+        while self.size + size > self.capacity {
+            let lru = self
+                .entries
+                .iter()
+                .min_by_key(|(_, e)| e.last_used)
+                .map(|(k, _)| k.clone())
+                .expect("cache size is positive without entries");
+            self.remove(&lru);
+        }
+        self.size += size;
+        self.entries.insert(
+            key,
+            Entry {
+                batches: batches.clone(),
+                size,
+                created: Instant::now(),
+                last_used: self.tick,
+            },
+        );
+        Ok(batches)
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.size = 0;
+    }
+
+    fn remove(&mut self, key: &Key) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.size -= entry.size;
+        }
+    }
+}
+
+/// Print the query back from its syntax tree, so that the same query formatted differently
+/// shares the entry while its literals are kept as they are. Queries that do not parse are only
+/// trimmed.
+fn normalize_query(query: &str) -> String {
+    match Parser::parse_sql(&GenericDialect {}, query) {
+        Ok(ast) => ast
+            .iter()
+            .map(|statement| statement.to_string())
+            .collect::<Vec<_>>()
+            .join("; "),
+        Err(_) => query.trim().to_string(),
+    }
+}
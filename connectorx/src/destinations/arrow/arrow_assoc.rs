@@ -1,11 +1,21 @@
+use crate::destinations::DecimalPrecision;
 use crate::errors::{ConnectorAgentError, Result};
+use crate::point::Point;
+use crate::range::Range;
 use arrow::array::{
-    ArrayBuilder, BooleanBuilder, Float64Builder, Int32Builder, Int64Builder, StringBuilder,
+    Array, ArrayBuilder, ArrayRef, BinaryBuilder, BooleanArray, BooleanBuilder, Date32Builder,
+    DecimalBuilder, Float64Builder, Int32Builder, Int64Builder, StringBuilder, StructArray,
+    TimestampNanosecondBuilder,
 };
 use arrow::datatypes::DataType as ArrowDataType;
-use arrow::datatypes::Field;
-use chrono::{Date, DateTime, Utc};
-use fehler::throws;
+use arrow::datatypes::{DateUnit, Field, TimeUnit};
+use chrono::{Date, DateTime, NaiveDate, Utc};
+use fehler::{throw, throws};
+use rust_decimal::Decimal;
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+use std::sync::Arc;
 
 /// Associate arrow builder with native type
 pub trait ArrowAssoc {
@@ -29,7 +39,7 @@ impl ArrowAssoc for i32 {
     }
 
     fn field(header: &str) -> Field {
-        Field::new(header, ArrowDataType::UInt64, false)
+        Field::new(header, ArrowDataType::Int32, false)
     }
 }
 
@@ -46,7 +56,7 @@ impl ArrowAssoc for Option<i32> {
     }
 
     fn field(header: &str) -> Field {
-        Field::new(header, ArrowDataType::UInt64, true)
+        Field::new(header, ArrowDataType::Int32, true)
     }
 }
 
@@ -80,7 +90,7 @@ impl ArrowAssoc for Option<i64> {
     }
 
     fn field(header: &str) -> Field {
-        Field::new(header, ArrowDataType::Int64, false)
+        Field::new(header, ArrowDataType::Int64, true)
     }
 }
 
@@ -189,66 +199,513 @@ impl ArrowAssoc for Option<String> {
     }
 }
 
+impl ArrowAssoc for Vec<u8> {
+    type Builder = BinaryBuilder;
+
+    fn builder(nrows: usize) -> BinaryBuilder {
+        BinaryBuilder::new(nrows)
+    }
+
+    #[throws(ConnectorAgentError)]
+    fn append(builder: &mut Self::Builder, value: Vec<u8>) {
+        builder.append_value(&value)?;
+    }
+
+    fn field(header: &str) -> Field {
+        Field::new(header, ArrowDataType::Binary, false)
+    }
+}
+
+impl ArrowAssoc for Option<Vec<u8>> {
+    type Builder = BinaryBuilder;
+
+    fn builder(nrows: usize) -> BinaryBuilder {
+        BinaryBuilder::new(nrows)
+    }
+
+    #[throws(ConnectorAgentError)]
+    fn append(builder: &mut Self::Builder, value: Self) {
+        match value {
+            Some(b) => builder.append_value(&b)?,
+            None => builder.append_null()?,
+        }
+    }
+
+    fn field(header: &str) -> Field {
+        Field::new(header, ArrowDataType::Binary, true)
+    }
+}
+
+fn naive_date_to_arrow(nd: NaiveDate) -> i32 {
+    nd.signed_duration_since(NaiveDate::from_ymd(1970, 1, 1))
+        .num_days() as i32
+}
+
+/// Nanoseconds since the epoch, which only covers the years 1677 to 2262.
+#[throws(ConnectorAgentError)]
+fn datetime_to_arrow(dt: DateTime<Utc>) -> i64 {
+    match dt
+        .timestamp()
+        .checked_mul(1_000_000_000)
+        .and_then(|ns| ns.checked_add(dt.timestamp_subsec_nanos() as i64))
+    {
+        Some(ns) => ns,
+        None => throw!(ConnectorAgentError::TimestampOutOfRange(dt)),
+    }
+}
+
 impl ArrowAssoc for DateTime<Utc> {
-    type Builder = Float64Builder;
+    type Builder = TimestampNanosecondBuilder;
 
-    fn builder(_nrows: usize) -> Float64Builder {
-        unimplemented!()
+    fn builder(nrows: usize) -> TimestampNanosecondBuilder {
+        TimestampNanosecondBuilder::new(nrows)
     }
 
-    fn append(_builder: &mut Self::Builder, _value: DateTime<Utc>) -> Result<()> {
-        unimplemented!()
+    #[throws(ConnectorAgentError)]
+    fn append(builder: &mut Self::Builder, value: DateTime<Utc>) {
+        builder.append_value(datetime_to_arrow(value)?)?;
     }
 
-    fn field(_header: &str) -> Field {
-        unimplemented!()
+    fn field(header: &str) -> Field {
+        Field::new(
+            header,
+            ArrowDataType::Timestamp(TimeUnit::Nanosecond, None),
+            false,
+        )
     }
 }
 
 impl ArrowAssoc for Option<DateTime<Utc>> {
-    type Builder = Float64Builder;
+    type Builder = TimestampNanosecondBuilder;
 
-    fn builder(_nrows: usize) -> Float64Builder {
-        unimplemented!()
+    fn builder(nrows: usize) -> TimestampNanosecondBuilder {
+        TimestampNanosecondBuilder::new(nrows)
     }
 
-    fn append(_builder: &mut Self::Builder, _value: Option<DateTime<Utc>>) -> Result<()> {
-        unimplemented!()
+    #[throws(ConnectorAgentError)]
+    fn append(builder: &mut Self::Builder, value: Option<DateTime<Utc>>) {
+        builder.append_option(value.map(datetime_to_arrow).transpose()?)?;
     }
 
-    fn field(_header: &str) -> Field {
-        unimplemented!()
+    fn field(header: &str) -> Field {
+        Field::new(
+            header,
+            ArrowDataType::Timestamp(TimeUnit::Nanosecond, None),
+            true,
+        )
     }
 }
 
 impl ArrowAssoc for Date<Utc> {
-    type Builder = Float64Builder;
+    type Builder = Date32Builder;
 
-    fn builder(_nrows: usize) -> Float64Builder {
-        unimplemented!()
+    fn builder(nrows: usize) -> Date32Builder {
+        Date32Builder::new(nrows)
     }
 
-    fn append(_builder: &mut Self::Builder, _value: Date<Utc>) -> Result<()> {
-        unimplemented!()
+    #[throws(ConnectorAgentError)]
+    fn append(builder: &mut Self::Builder, value: Date<Utc>) {
+        builder.append_value(naive_date_to_arrow(value.naive_utc()))?;
     }
 
-    fn field(_header: &str) -> Field {
-        unimplemented!()
+    fn field(header: &str) -> Field {
+        Field::new(header, ArrowDataType::Date32(DateUnit::Day), false)
     }
 }
 
 impl ArrowAssoc for Option<Date<Utc>> {
-    type Builder = Float64Builder;
+    type Builder = Date32Builder;
+
+    fn builder(nrows: usize) -> Date32Builder {
+        Date32Builder::new(nrows)
+    }
+
+    #[throws(ConnectorAgentError)]
+    fn append(builder: &mut Self::Builder, value: Option<Date<Utc>>) {
+        builder.append_option(value.map(|x| naive_date_to_arrow(x.naive_utc())))?;
+    }
+
+    fn field(header: &str) -> Field {
+        Field::new(header, ArrowDataType::Date32(DateUnit::Day), true)
+    }
+}
+
+impl ArrowAssoc for NaiveDate {
+    type Builder = Date32Builder;
+
+    fn builder(nrows: usize) -> Date32Builder {
+        Date32Builder::new(nrows)
+    }
+
+    #[throws(ConnectorAgentError)]
+    fn append(builder: &mut Self::Builder, value: NaiveDate) {
+        builder.append_value(naive_date_to_arrow(value))?;
+    }
+
+    fn field(header: &str) -> Field {
+        Field::new(header, ArrowDataType::Date32(DateUnit::Day), false)
+    }
+}
+
+impl ArrowAssoc for Option<NaiveDate> {
+    type Builder = Date32Builder;
+
+    fn builder(nrows: usize) -> Date32Builder {
+        Date32Builder::new(nrows)
+    }
+
+    #[throws(ConnectorAgentError)]
+    fn append(builder: &mut Self::Builder, value: Option<NaiveDate>) {
+        builder.append_option(value.map(naive_date_to_arrow))?;
+    }
+
+    fn field(header: &str) -> Field {
+        Field::new(header, ArrowDataType::Date32(DateUnit::Day), true)
+    }
+}
+
+/// Ranges are written as a struct of their bounds, with a null bound for an unbounded side.
+fn range_fields<T>() -> Vec<Field>
+where
+    Option<T>: ArrowAssoc,
+{
+    vec![
+        <Option<T> as ArrowAssoc>::field("lower"),
+        <Option<T> as ArrowAssoc>::field("upper"),
+        Field::new("lower_inc", ArrowDataType::Boolean, false),
+        Field::new("upper_inc", ArrowDataType::Boolean, false),
+        Field::new("empty", ArrowDataType::Boolean, false),
+    ]
+}
+
+/// Builds the struct array of `Range<T>`, using the builder of `Option<T>` for the bounds.
+pub struct RangeBuilder<T>
+where
+    Option<T>: ArrowAssoc,
+{
+    lower: <Option<T> as ArrowAssoc>::Builder,
+    upper: <Option<T> as ArrowAssoc>::Builder,
+    lower_inc: BooleanBuilder,
+    upper_inc: BooleanBuilder,
+    empty: BooleanBuilder,
+    valid: Vec<bool>,
+    _marker: PhantomData<T>,
+}
+
+impl<T> RangeBuilder<T>
+where
+    Option<T>: ArrowAssoc,
+{
+    fn new(nrows: usize) -> Self {
+        Self {
+            lower: <Option<T> as ArrowAssoc>::builder(nrows),
+            upper: <Option<T> as ArrowAssoc>::builder(nrows),
+            lower_inc: BooleanBuilder::new(nrows),
+            upper_inc: BooleanBuilder::new(nrows),
+            empty: BooleanBuilder::new(nrows),
+            valid: Vec::with_capacity(nrows),
+            _marker: PhantomData,
+        }
+    }
+
+    #[throws(ConnectorAgentError)]
+    fn append(&mut self, value: Option<Range<T>>) {
+        let valid = value.is_some();
+        let range = value.unwrap_or_default();
+        <Option<T> as ArrowAssoc>::append(&mut self.lower, range.lower)?;
+        <Option<T> as ArrowAssoc>::append(&mut self.upper, range.upper)?;
+        self.lower_inc.append_value(range.lower_inc)?;
+        self.upper_inc.append_value(range.upper_inc)?;
+        self.empty.append_value(range.empty)?;
+        self.valid.push(valid);
+    }
+}
+
+impl<T> ArrayBuilder for RangeBuilder<T>
+where
+    T: Send + 'static,
+    Option<T>: ArrowAssoc,
+{
+    fn len(&self) -> usize {
+        self.valid.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.valid.is_empty()
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        let columns = vec![
+            ArrayBuilder::finish(&mut self.lower),
+            ArrayBuilder::finish(&mut self.upper),
+            ArrayBuilder::finish(&mut self.lower_inc),
+            ArrayBuilder::finish(&mut self.upper_inc),
+            ArrayBuilder::finish(&mut self.empty),
+        ];
+        let valid = BooleanArray::from(std::mem::take(&mut self.valid));
+        let null_buffer = valid.data().buffers()[0].clone();
+        Arc::new(StructArray::from((
+            range_fields::<T>()
+                .into_iter()
+                .zip(columns)
+                .collect::<Vec<_>>(),
+            null_buffer,
+        )))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn into_box_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+impl<T> ArrowAssoc for Range<T>
+where
+    T: Send + 'static,
+    Option<T>: ArrowAssoc,
+{
+    type Builder = RangeBuilder<T>;
+
+    fn builder(nrows: usize) -> RangeBuilder<T> {
+        RangeBuilder::<T>::new(nrows)
+    }
 
-    fn builder(_nrows: usize) -> Float64Builder {
-        unimplemented!()
+    fn append(builder: &mut RangeBuilder<T>, value: Range<T>) -> Result<()> {
+        builder.append(Some(value))
     }
 
-    fn append(_builder: &mut Self::Builder, _value: Option<Date<Utc>>) -> Result<()> {
-        unimplemented!()
+    fn field(header: &str) -> Field {
+        Field::new(header, ArrowDataType::Struct(range_fields::<T>()), false)
+    }
+}
+
+impl<T> ArrowAssoc for Option<Range<T>>
+where
+    T: Send + 'static,
+    Option<T>: ArrowAssoc,
+{
+    type Builder = RangeBuilder<T>;
+
+    fn builder(nrows: usize) -> RangeBuilder<T> {
+        RangeBuilder::<T>::new(nrows)
+    }
+
+    fn append(builder: &mut RangeBuilder<T>, value: Option<Range<T>>) -> Result<()> {
+        builder.append(value)
+    }
+
+    fn field(header: &str) -> Field {
+        Field::new(header, ArrowDataType::Struct(range_fields::<T>()), true)
+    }
+}
+
+/// Points are written as a struct of their coordinates, the separated point layout of GeoArrow.
+fn point_fields() -> Vec<Field> {
+    vec![
+        Field::new("x", ArrowDataType::Float64, false),
+        Field::new("y", ArrowDataType::Float64, false),
+    ]
+}
+
+fn point_field(header: &str, nullable: bool) -> Field {
+    let mut field = Field::new(header, ArrowDataType::Struct(point_fields()), nullable);
+    let mut metadata = BTreeMap::new();
+    metadata.insert(
+        "ARROW:extension:name".to_string(),
+        "geoarrow.point".to_string(),
+    );
+    field.set_metadata(Some(metadata));
+    field
+}
+
+/// Builds the struct array of `Point`.
+pub struct PointBuilder {
+    x: Float64Builder,
+    y: Float64Builder,
+    valid: Vec<bool>,
+}
+
+impl PointBuilder {
+    fn new(nrows: usize) -> Self {
+        Self {
+            x: Float64Builder::new(nrows),
+            y: Float64Builder::new(nrows),
+            valid: Vec::with_capacity(nrows),
+        }
+    }
+
+    #[throws(ConnectorAgentError)]
+    fn append(&mut self, value: Option<Point>) {
+        let valid = value.is_some();
+        let point = value.unwrap_or_default();
+        self.x.append_value(point.x)?;
+        self.y.append_value(point.y)?;
+        self.valid.push(valid);
+    }
+}
+
+impl ArrayBuilder for PointBuilder {
+    fn len(&self) -> usize {
+        self.valid.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.valid.is_empty()
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        let columns = vec![
+            ArrayBuilder::finish(&mut self.x),
+            ArrayBuilder::finish(&mut self.y),
+        ];
+        let valid = BooleanArray::from(std::mem::take(&mut self.valid));
+        let null_buffer = valid.data().buffers()[0].clone();
+        Arc::new(StructArray::from((
+            point_fields().into_iter().zip(columns).collect::<Vec<_>>(),
+            null_buffer,
+        )))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn into_box_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+impl ArrowAssoc for Point {
+    type Builder = PointBuilder;
+
+    fn builder(nrows: usize) -> PointBuilder {
+        PointBuilder::new(nrows)
+    }
+
+    fn append(builder: &mut PointBuilder, value: Point) -> Result<()> {
+        builder.append(Some(value))
+    }
+
+    fn field(header: &str) -> Field {
+        point_field(header, false)
+    }
+}
+
+impl ArrowAssoc for Option<Point> {
+    type Builder = PointBuilder;
+
+    fn builder(nrows: usize) -> PointBuilder {
+        PointBuilder::new(nrows)
+    }
+
+    fn append(builder: &mut PointBuilder, value: Option<Point>) -> Result<()> {
+        builder.append(value)
+    }
+
+    fn field(header: &str) -> Field {
+        point_field(header, true)
+    }
+}
+
+/// Builds the decimal array of a column at its `DecimalPrecision`, which `DecimalBuilder` keeps
+/// to itself.
+pub struct DecimalColumnBuilder {
+    builder: DecimalBuilder,
+    decimal: DecimalPrecision,
+}
+
+impl DecimalColumnBuilder {
+    pub fn new(nrows: usize, decimal: DecimalPrecision) -> Self {
+        Self {
+            builder: DecimalBuilder::new(nrows, decimal.precision, decimal.scale),
+            decimal,
+        }
+    }
+
+    #[throws(ConnectorAgentError)]
+    fn append(&mut self, value: Option<Decimal>) {
+        match value {
+            Some(v) => self.builder.append_value(self.decimal.mantissa(v)?)?,
+            None => self.builder.append_null()?,
+        }
+    }
+}
+
+impl ArrayBuilder for DecimalColumnBuilder {
+    fn len(&self) -> usize {
+        self.builder.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.builder.is_empty()
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        ArrayBuilder::finish(&mut self.builder)
     }
 
-    fn field(_header: &str) -> Field {
-        unimplemented!()
+    fn as_any(&self) -> &dyn Any {
+        self
     }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn into_box_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+/// Decimals are written at `DecimalPrecision::default()` unless the destination is told the
+/// precision of the column, see `ArrowDestination::decimal_precision`.
+impl ArrowAssoc for Decimal {
+    type Builder = DecimalColumnBuilder;
+
+    fn builder(nrows: usize) -> DecimalColumnBuilder {
+        DecimalColumnBuilder::new(nrows, DecimalPrecision::default())
+    }
+
+    fn append(builder: &mut Self::Builder, value: Decimal) -> Result<()> {
+        builder.append(Some(value))
+    }
+
+    fn field(header: &str) -> Field {
+        decimal_field(header, DecimalPrecision::default(), false)
+    }
+}
+
+impl ArrowAssoc for Option<Decimal> {
+    type Builder = DecimalColumnBuilder;
+
+    fn builder(nrows: usize) -> DecimalColumnBuilder {
+        DecimalColumnBuilder::new(nrows, DecimalPrecision::default())
+    }
+
+    fn append(builder: &mut Self::Builder, value: Option<Decimal>) -> Result<()> {
+        builder.append(value)
+    }
+
+    fn field(header: &str) -> Field {
+        decimal_field(header, DecimalPrecision::default(), true)
+    }
+}
+
+pub fn decimal_field(header: &str, decimal: DecimalPrecision, nullable: bool) -> Field {
+    Field::new(
+        header,
+        ArrowDataType::Decimal(decimal.precision, decimal.scale),
+        nullable,
+    )
 }
@@ -0,0 +1,91 @@
+use crate::errors::{ConnectorAgentError, Result};
+use anyhow::anyhow;
+use arrow::array::{Array, ArrayData, ArrayRef, DictionaryArray, Int32Array, StringArray};
+use arrow::datatypes::{DataType as ArrowDataType, Int32Type};
+use arrow::record_batch::RecordBatch;
+use fehler::{throw, throws};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Rewrite the `Dictionary(Int32, Utf8)` columns of `batches` so that all the batches reference
+/// one dictionary, holding the values of every batch in order of appearance. Batches finished
+/// per partition carry their own dictionaries, so the same key may stand for different values
+/// across batches. Other columns are left untouched, dictionaries of other key or value types
+/// are an error.
+#[throws(ConnectorAgentError)]
+pub fn unify_dictionaries(batches: Vec<RecordBatch>) -> Vec<RecordBatch> {
+    let schema = match batches.first() {
+        Some(batch) => batch.schema(),
+        None => return vec![],
+    };
+    let mut columns: Vec<Vec<ArrayRef>> = batches.iter().map(|b| b.columns().to_vec()).collect();
+
+    for (i, field) in schema.fields().iter().enumerate() {
+        match field.data_type() {
+            ArrowDataType::Dictionary(k, v)
+                if **k == ArrowDataType::Int32 && **v == ArrowDataType::Utf8 => {}
+            dt @ ArrowDataType::Dictionary(_, _) => throw!(anyhow!(
+                "cannot unify dictionaries of type {:?}, only Dictionary(Int32, Utf8) is supported",
+                dt
+            )),
+            _ => continue,
+        }
+
+        let mut index: HashMap<String, i32> = HashMap::new();
+        let mut values: Vec<String> = vec![];
+        let mut keys = vec![];
+        for batch in &columns {
+            let array = batch[i]
+                .as_any()
+                .downcast_ref::<DictionaryArray<Int32Type>>()
+                .ok_or_else(|| anyhow!("cannot cast arrow array for dictionary unification"))?;
+            let dict = array.values();
+            let dict = dict
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .ok_or_else(|| anyhow!("cannot cast arrow dictionary values to strings"))?;
+
+            // position in the unified dictionary of each value of this batch's dictionary
+            let mapping: Vec<i32> = (0..dict.len())
+                .map(|j| {
+                    let value = dict.value(j);
+                    *index.entry(value.to_string()).or_insert_with(|| {
+                        values.push(value.to_string());
+                        values.len() as i32 - 1
+                    })
+                })
+                .collect();
+
+            let batch_keys = array.keys_array();
+            let remapped: Vec<Option<i32>> = (0..batch_keys.len())
+                .map(|j| match batch_keys.is_null(j) {
+                    true => None,
+                    false => Some(mapping[batch_keys.value(j) as usize]),
+                })
+                .collect();
+            keys.push(Int32Array::from(remapped));
+        }
+
+        let dict: ArrayRef = Arc::new(StringArray::from(
+            values.iter().map(|v| v.as_str()).collect::<Vec<_>>(),
+        ));
+        for (batch, keys) in columns.iter_mut().zip(keys) {
+            let data = keys.data();
+            let unified = ArrayData::new(
+                field.data_type().clone(),
+                keys.len(),
+                Some(keys.null_count()),
+                data.null_buffer().cloned(),
+                0,
+                data.buffers().to_vec(),
+                vec![dict.data()],
+            );
+            batch[i] = Arc::new(DictionaryArray::<Int32Type>::from(Arc::new(unified)));
+        }
+    }
+
+    columns
+        .into_iter()
+        .map(|columns| Ok(RecordBatch::try_new(schema.clone(), columns)?))
+        .collect::<Result<Vec<_>>>()?
+}
@@ -0,0 +1,73 @@
+use super::ArrowBatchReader;
+use crate::errors::{ConnectorAgentError, Result};
+use arrow::datatypes::SchemaRef;
+use arrow::ffi::{ArrowArray, FFI_ArrowArray, FFI_ArrowSchema};
+use arrow::record_batch::RecordBatch;
+use fehler::throws;
+use std::convert::TryFrom;
+
+/// The columns of a `RecordBatch` exported through the Arrow C Data Interface, sharing the
+/// buffers of the batch instead of copying them. The exported structs are released on drop
+/// unless they are handed over to a consumer with `export`.
+pub struct FFIRecordBatch {
+    schema: SchemaRef,
+    columns: Vec<ArrowArray>,
+}
+
+impl FFIRecordBatch {
+    #[throws(ConnectorAgentError)]
+    pub fn try_new(batch: &RecordBatch) -> Self {
+        let columns = batch
+            .columns()
+            .iter()
+            .map(|column| Ok(ArrowArray::try_from(column.data().as_ref().clone())?))
+            .collect::<Result<Vec<_>>>()?;
+        FFIRecordBatch {
+            schema: batch.schema(),
+            columns,
+        }
+    }
+
+    pub fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    pub fn num_columns(&self) -> usize {
+        self.columns.len()
+    }
+
+    /// Hand the `ArrowArray` and `ArrowSchema` structs of each column over to the consumer,
+    /// e.g. `pyarrow.Array._import_from_c`.
+    ///
+    /// # Safety
+    /// The consumer takes ownership of the returned structs and must call their `release`
+    /// callback exactly once, the buffers of the batch are leaked otherwise.
+    pub unsafe fn export(self) -> Vec<(*const FFI_ArrowArray, *const FFI_ArrowSchema)> {
+        self.columns.into_iter().map(ArrowArray::into_raw).collect()
+    }
+}
+
+/// An iterator exporting the partitions written into an `ArrowDestination`, one
+/// `FFIRecordBatch` per partition, finished only when it is read.
+pub struct ArrowFFIReader {
+    reader: ArrowBatchReader,
+}
+
+impl ArrowFFIReader {
+    pub(super) fn new(reader: ArrowBatchReader) -> Self {
+        ArrowFFIReader { reader }
+    }
+}
+
+impl Iterator for ArrowFFIReader {
+    type Item = Result<FFIRecordBatch>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let batch = self.reader.next()?;
+        Some(
+            batch
+                .map_err(Into::into)
+                .and_then(|b| FFIRecordBatch::try_new(&b)),
+        )
+    }
+}
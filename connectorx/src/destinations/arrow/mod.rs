@@ -1,42 +1,111 @@
-use super::{Consume, Destination, DestinationPartition};
+use super::{Consume, DecimalPrecision, Destination, DestinationPartition, NonFinitePolicy};
 use crate::data_order::DataOrder;
 use crate::dummy_typesystem::DummyTypeSystem;
 use crate::errors::{ConnectorAgentError, Result};
 use crate::typesystem::{Realize, TypeAssoc, TypeSystem};
 use anyhow::anyhow;
-use arrow::datatypes::Schema;
+use arrow::array::{Array, ArrayRef, Float64Array, Int32Array};
+use arrow::compute::concat;
+use arrow::datatypes::{DataType as ArrowDataType, Field, Schema};
 use arrow::record_batch::RecordBatch;
-use arrow_assoc::ArrowAssoc;
-use fehler::throws;
+use fehler::{throw, throws};
 use funcs::{FFinishBuilder, FNewBuilder, FNewField};
 use itertools::Itertools;
 use std::any::Any;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 
 mod arrow_assoc;
+mod dictionary;
+mod ffi;
 mod funcs;
+mod reader;
+mod sentinel;
+
+use arrow_assoc::{decimal_field, DecimalColumnBuilder};
+
+pub use arrow_assoc::ArrowAssoc;
+pub use dictionary::unify_dictionaries;
+pub use ffi::{ArrowFFIReader, FFIRecordBatch};
+pub use reader::ArrowBatchReader;
+pub use sentinel::NullSentinel;
 
 type Builder = Box<dyn Any + Send>;
 type Builders = Vec<Builder>;
 
 pub struct ArrowDestination {
     nrows: usize,
+    names: Vec<String>,
     schema: Vec<DummyTypeSystem>,
     builders: Vec<Builders>,
+    null_sentinels: HashMap<String, NullSentinel>,
+    decimals: HashMap<String, DecimalPrecision>,
+    non_finite: NonFinitePolicy,
+    source_types: Vec<String>,
+    partition_id: bool,
 }
 
+/// The field metadata key holding the database type name of the column.
+pub const SOURCE_TYPE_KEY: &str = "connectorx.source_type";
+
+/// The name of the column added by `ArrowDestination::partition_id_column`.
+pub const PARTITION_ID_COLUMN: &str = "__partition_id";
+
 impl ArrowDestination {
     pub fn new() -> Self {
         ArrowDestination {
             nrows: 0,
+            names: vec![],
             schema: vec![],
             builders: vec![],
+            null_sentinels: HashMap::new(),
+            decimals: HashMap::new(),
+            non_finite: NonFinitePolicy::Preserve,
+            source_types: vec![],
+            partition_id: false,
         }
     }
+
+    /// Set how non-finite floats are written, defaults to `NonFinitePolicy::Preserve` since
+    /// arrow can represent them. Under `NonFinitePolicy::Null` float columns become nullable.
+    pub fn non_finite_policy(&mut self, policy: NonFinitePolicy) {
+        self.non_finite = policy;
+    }
+
+    /// Record the database type name of each column (e.g. from `PostgresSource::type_names`)
+    /// in the metadata of the arrow fields under `SOURCE_TYPE_KEY`.
+    pub fn source_type_names(&mut self, type_names: Vec<String>) {
+        self.source_types = type_names;
+    }
+
+    /// Append a non-nullable Int32 column named `PARTITION_ID_COLUMN` holding the index of the
+    /// partition each row is read from, e.g. to find skewed partitions.
+    pub fn partition_id_column(&mut self, enable: bool) {
+        self.partition_id = enable;
+    }
+
+    /// The names of the columns, available after allocation.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Write `value` instead of null into `column` and mark the column as non-nullable.
+    /// This needs to be set before allocation, where the type of `value` is checked against the column.
+    pub fn null_sentinel(&mut self, column: &str, value: NullSentinel) {
+        self.null_sentinels.insert(column.to_string(), value);
+    }
+
+    /// Write the decimal `column` at `decimal` instead of `DecimalPrecision::default()`, e.g. at
+    /// the `NUMERIC(precision, scale)` it is declared with. Values that do not fit fail the write.
+    /// This needs to be set before allocation, where the column is checked to be a decimal.
+    pub fn decimal_precision(&mut self, column: &str, decimal: DecimalPrecision) {
+        self.decimals.insert(column.to_string(), decimal);
+    }
 }
 
 impl Destination for ArrowDestination {
     const DATA_ORDERS: &'static [DataOrder] = &[DataOrder::ColumnMajor, DataOrder::RowMajor];
+    const GROWABLE: bool = true;
     type TypeSystem = DummyTypeSystem;
     type Partition<'a> = ArrowPartitionWriter<'a>;
 
@@ -44,13 +113,39 @@ impl Destination for ArrowDestination {
     fn allocate<S: AsRef<str>>(
         &mut self,
         nrows: usize,
-        _names: &[S],
+        names: &[S],
         schema: &[DummyTypeSystem],
         _data_order: DataOrder,
     ) {
         // cannot really create builders since do not know each partition size here
         self.nrows = nrows;
+        self.names = names.iter().map(|n| n.as_ref().to_string()).collect();
         self.schema = schema.to_vec();
+
+        for (column, sentinel) in &self.null_sentinels {
+            match self.names.iter().position(|n| n == column) {
+                Some(i) => sentinel.check(self.schema[i])?,
+                None => throw!(anyhow!(
+                    "cannot set null sentinel for unknown column {}",
+                    column
+                )),
+            }
+        }
+
+        for (column, decimal) in &self.decimals {
+            decimal.check()?;
+            match self.names.iter().position(|n| n == column) {
+                Some(i) if matches!(self.schema[i], DummyTypeSystem::Decimal(_)) => {}
+                Some(i) => throw!(ConnectorAgentError::TypeCheckFailed(
+                    format!("{:?}", self.schema[i]),
+                    std::any::type_name::<rust_decimal::Decimal>()
+                )),
+                None => throw!(anyhow!(
+                    "cannot set decimal precision for unknown column {}",
+                    column
+                )),
+            }
+        }
     }
 
     #[throws(ConnectorAgentError)]
@@ -59,12 +154,7 @@ impl Destination for ArrowDestination {
         assert_eq!(self.builders.len(), 0);
 
         for &c in counts {
-            let builders = self
-                .schema
-                .iter()
-                .map(|&dt| Ok(Realize::<FNewBuilder>::realize(dt)?(c)))
-                .collect::<Result<Vec<_>>>()?;
-
+            let builders = self.new_builders(c)?;
             self.builders.push(builders);
         }
 
@@ -84,27 +174,231 @@ impl Destination for ArrowDestination {
 impl ArrowDestination {
     #[throws(ConnectorAgentError)]
     pub fn finish(self, headers: Vec<String>) -> Vec<RecordBatch> {
-        let fields = self
-            .schema
+        let (arrow_schema, partitions) = self.finish_partitions(headers)?;
+        let arrow_schema = Arc::new(arrow_schema);
+        partitions
+            .into_iter()
+            .map(|columns| Ok(RecordBatch::try_new(Arc::clone(&arrow_schema), columns)?))
+            .collect::<Result<Vec<_>>>()?
+    }
+
+    /// A batch without rows carrying the resolved schema, e.g. after `Dispatcher::get_meta`.
+    #[throws(ConnectorAgentError)]
+    pub fn empty_batch(self) -> RecordBatch {
+        let headers = self.names.clone();
+        let (arrow_schema, columns) = self.finish_arrays(headers)?;
+        RecordBatch::try_new(Arc::new(arrow_schema), columns)?
+    }
+
+    /// Like `finish`, but returns one array per column with the partitions concatenated,
+    /// instead of assembling them into `RecordBatch`es.
+    #[throws(ConnectorAgentError)]
+    pub fn finish_arrays(self, headers: Vec<String>) -> (Schema, Vec<ArrayRef>) {
+        let schema = self.schema.clone();
+        let empty = schema
             .iter()
-            .zip_eq(headers)
-            .map(|(&dt, h)| Ok(Realize::<FNewField>::realize(dt)?(h.as_str())))
+            .zip(self.new_builders(0)?)
+            .map(|(&dt, builder)| Realize::<FFinishBuilder>::realize(dt)?(builder))
             .collect::<Result<Vec<_>>>()?;
+        let (arrow_schema, partitions) = self.finish_partitions(headers)?;
 
-        let arrow_schema = Arc::new(Schema::new(fields));
-        let schema = self.schema.clone();
-        self.builders
+        let concat_column = |i: usize, empty: &dyn Fn() -> Result<ArrayRef>| {
+            let arrays: Vec<ArrayRef> = partitions.iter().map(|p| p[i].clone()).collect();
+            match arrays.len() {
+                0 => empty(),
+                1 => Ok(arrays[0].clone()),
+                _ => Ok(concat(
+                    &arrays.iter().map(|a| a.as_ref()).collect::<Vec<_>>(),
+                )?),
+            }
+        };
+
+        let mut columns = (0..schema.len())
+            .map(|i| concat_column(i, &|| Ok(empty[i].clone())))
+            .collect::<Result<Vec<_>>>()?;
+        if arrow_schema.fields().len() > schema.len() {
+            columns.push(concat_column(schema.len(), &|| {
+                Ok(Arc::new(Int32Array::from(Vec::<i32>::new())) as ArrayRef)
+            })?);
+        }
+
+        (arrow_schema, columns)
+    }
+
+    /// Like `finish`, but hands out a `RecordBatchReader` finishing one partition per batch,
+    /// so that it can be passed to any API consuming arrow record batches.
+    #[throws(ConnectorAgentError)]
+    pub fn record_batch_reader(self, headers: Vec<String>) -> ArrowBatchReader {
+        let fields = self.finish_fields(headers)?;
+        let sentinels = self.column_sentinels();
+        ArrowBatchReader::new(
+            Arc::new(Schema::new(fields)),
+            self.schema,
+            sentinels,
+            self.non_finite,
+            self.partition_id,
+            self.builders,
+        )
+    }
+
+    /// Like `record_batch_reader`, but exports each batch through the Arrow C Data Interface,
+    /// e.g. to hand it over to pyarrow without copying.
+    #[throws(ConnectorAgentError)]
+    pub fn ffi_reader(self, headers: Vec<String>) -> ArrowFFIReader {
+        ArrowFFIReader::new(self.record_batch_reader(headers)?)
+    }
+
+    #[throws(ConnectorAgentError)]
+    fn finish_partitions(self, headers: Vec<String>) -> (Schema, Vec<Vec<ArrayRef>>) {
+        let fields = self.finish_fields(headers)?;
+        let sentinels = self.column_sentinels();
+        let non_finite = self.non_finite;
+        let partition_id = self.partition_id;
+        let schema = self.schema;
+
+        let partitions = self
+            .builders
             .into_iter()
-            .map(|pbuilder| {
-                let columns = pbuilder
-                    .into_iter()
-                    .zip(schema.iter())
-                    .map(|(builder, &dt)| Ok(Realize::<FFinishBuilder>::realize(dt)?(builder)?))
-                    .collect::<Result<Vec<_>>>()?;
-                Ok(RecordBatch::try_new(Arc::clone(&arrow_schema), columns)?)
+            .enumerate()
+            .map(|(i, pbuilder)| {
+                let mut columns = finish_builders(pbuilder, &schema, &sentinels, non_finite)?;
+                if partition_id {
+                    columns.push(partition_id_array(i, &columns));
+                }
+                Ok(columns)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        (Schema::new(fields), partitions)
+    }
+
+    /// The builders of one partition of `nrows` rows.
+    #[throws(ConnectorAgentError)]
+    fn new_builders(&self, nrows: usize) -> Builders {
+        self.schema
+            .iter()
+            .zip(self.column_decimals())
+            .map(|(&dt, decimal)| match (dt, decimal) {
+                (DummyTypeSystem::Decimal(_), Some(decimal)) => {
+                    Ok(Box::new(DecimalColumnBuilder::new(nrows, decimal)) as Builder)
+                }
+                _ => Ok(Realize::<FNewBuilder>::realize(dt)?(nrows)),
             })
             .collect::<Result<Vec<_>>>()?
     }
+
+    fn column_decimals(&self) -> Vec<Option<DecimalPrecision>> {
+        self.names
+            .iter()
+            .map(|n| self.decimals.get(n).cloned())
+            .collect()
+    }
+
+    fn column_sentinels(&self) -> Vec<Option<NullSentinel>> {
+        self.names
+            .iter()
+            .map(|n| self.null_sentinels.get(n).cloned())
+            .collect()
+    }
+
+    #[throws(ConnectorAgentError)]
+    fn finish_fields(&self, headers: Vec<String>) -> Vec<Field> {
+        let sentinels = self.column_sentinels();
+        let decimals = self.column_decimals();
+        let non_finite = self.non_finite;
+
+        let mut fields = self
+            .schema
+            .iter()
+            .zip_eq(headers)
+            .enumerate()
+            .map(|(i, (&dt, h))| {
+                let mut field = Realize::<FNewField>::realize(dt)?(h.as_str());
+                if let Some(decimal) = decimals[i] {
+                    field = decimal_field(h.as_str(), decimal, field.is_nullable());
+                }
+                let nullable = match (sentinels.get(i), dt) {
+                    (Some(Some(_)), _) => false,
+                    (_, DummyTypeSystem::F64(_)) if non_finite == NonFinitePolicy::Null => true,
+                    _ => field.is_nullable(),
+                };
+                // keep the metadata of the type, e.g. the extension name of points
+                let mut metadata = field.metadata().clone().unwrap_or_default();
+                let mut field = Field::new(field.name(), field.data_type().clone(), nullable);
+                if let Some(ty) = self.source_types.get(i) {
+                    metadata.insert(SOURCE_TYPE_KEY.to_string(), ty.clone());
+                }
+                if !metadata.is_empty() {
+                    field.set_metadata(Some(metadata));
+                }
+                Ok(field)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if self.partition_id {
+            fields.push(Field::new(PARTITION_ID_COLUMN, ArrowDataType::Int32, false));
+        }
+        fields
+    }
+}
+
+/// The partition id column for a partition with the given finished `columns`.
+fn partition_id_array(partition: usize, columns: &[ArrayRef]) -> ArrayRef {
+    let nrows = columns.first().map(|c| c.len()).unwrap_or(0);
+    Arc::new(Int32Array::from(vec![partition as i32; nrows]))
+}
+
+/// Finish the builders of one partition into arrays, applying the non-finite policy and null sentinels.
+#[throws(ConnectorAgentError)]
+fn finish_builders(
+    pbuilder: Builders,
+    schema: &[DummyTypeSystem],
+    sentinels: &[Option<NullSentinel>],
+    non_finite: NonFinitePolicy,
+) -> Vec<ArrayRef> {
+    pbuilder
+        .into_iter()
+        .zip(schema.iter())
+        .enumerate()
+        .map(|(i, (builder, &dt))| {
+            let mut array = Realize::<FFinishBuilder>::realize(dt)?(builder)?;
+            if let DummyTypeSystem::F64(_) = dt {
+                array = apply_non_finite_policy(non_finite, array)?;
+            }
+            match sentinels.get(i) {
+                Some(Some(sentinel)) => sentinel.fill(array),
+                _ => Ok(array),
+            }
+        })
+        .collect::<Result<Vec<_>>>()?
+}
+
+#[throws(ConnectorAgentError)]
+fn apply_non_finite_policy(policy: NonFinitePolicy, array: ArrayRef) -> ArrayRef {
+    let arr = array
+        .as_any()
+        .downcast_ref::<Float64Array>()
+        .ok_or_else(|| anyhow!("cannot cast arrow array for non-finite floats"))?;
+    let mut non_finite = (0..arr.len()).filter(|&i| !arr.is_null(i) && !arr.value(i).is_finite());
+
+    match policy {
+        NonFinitePolicy::Preserve => array,
+        NonFinitePolicy::Error => match non_finite.next() {
+            Some(i) => throw!(ConnectorAgentError::NonFiniteFloat(arr.value(i))),
+            None => array,
+        },
+        NonFinitePolicy::Null => match non_finite.next() {
+            Some(_) => {
+                let values: Vec<Option<f64>> = (0..arr.len())
+                    .map(|i| match arr.is_null(i) || !arr.value(i).is_finite() {
+                        true => None,
+                        false => Some(arr.value(i)),
+                    })
+                    .collect();
+                Arc::new(Float64Array::from(values)) as ArrayRef
+            }
+            None => array,
+        },
+    }
 }
 
 pub struct ArrowPartitionWriter<'a> {
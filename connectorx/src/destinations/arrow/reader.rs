@@ -0,0 +1,62 @@
+use super::{finish_builders, partition_id_array, Builders, NullSentinel};
+use crate::destinations::NonFinitePolicy;
+use crate::dummy_typesystem::DummyTypeSystem;
+use arrow::datatypes::SchemaRef;
+use arrow::error::{ArrowError, Result as ArrowResult};
+use arrow::record_batch::{RecordBatch, RecordBatchReader};
+use std::iter::Enumerate;
+use std::vec::IntoIter;
+
+/// A `RecordBatchReader` over the partitions written into an `ArrowDestination`.
+/// Each partition is finished into a `RecordBatch` only when it is read.
+pub struct ArrowBatchReader {
+    arrow_schema: SchemaRef,
+    schema: Vec<DummyTypeSystem>,
+    sentinels: Vec<Option<NullSentinel>>,
+    non_finite: NonFinitePolicy,
+    partition_id: bool,
+    partitions: Enumerate<IntoIter<Builders>>,
+}
+
+impl ArrowBatchReader {
+    pub(super) fn new(
+        arrow_schema: SchemaRef,
+        schema: Vec<DummyTypeSystem>,
+        sentinels: Vec<Option<NullSentinel>>,
+        non_finite: NonFinitePolicy,
+        partition_id: bool,
+        partitions: Vec<Builders>,
+    ) -> Self {
+        ArrowBatchReader {
+            arrow_schema,
+            schema,
+            sentinels,
+            non_finite,
+            partition_id,
+            partitions: partitions.into_iter().enumerate(),
+        }
+    }
+}
+
+impl Iterator for ArrowBatchReader {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (i, pbuilder) = self.partitions.next()?;
+        let columns = finish_builders(pbuilder, &self.schema, &self.sentinels, self.non_finite)
+            .map(|mut columns| {
+                if self.partition_id {
+                    columns.push(partition_id_array(i, &columns));
+                }
+                columns
+            })
+            .map_err(|e| ArrowError::ExternalError(Box::new(e)));
+        Some(columns.and_then(|columns| RecordBatch::try_new(self.arrow_schema.clone(), columns)))
+    }
+}
+
+impl RecordBatchReader for ArrowBatchReader {
+    fn schema(&self) -> SchemaRef {
+        self.arrow_schema.clone()
+    }
+}
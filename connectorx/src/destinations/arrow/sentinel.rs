@@ -0,0 +1,90 @@
+use crate::dummy_typesystem::DummyTypeSystem;
+use crate::errors::{ConnectorAgentError, Result};
+use anyhow::anyhow;
+use arrow::array::{Array, ArrayRef, BooleanArray, Float64Array, Int64Array, StringArray};
+use fehler::{throw, throws};
+use std::any::type_name;
+use std::sync::Arc;
+
+/// A value written in place of nulls, for consumers that cannot handle the arrow null bitmap.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NullSentinel {
+    I64(i64),
+    F64(f64),
+    Bool(bool),
+    String(String),
+}
+
+impl NullSentinel {
+    /// Check whether the sentinel can be written into a column of type `dt`.
+    #[throws(ConnectorAgentError)]
+    pub fn check(&self, dt: DummyTypeSystem) {
+        match (self, dt) {
+            (NullSentinel::I64(_), DummyTypeSystem::I64(_))
+            | (NullSentinel::F64(_), DummyTypeSystem::F64(_))
+            | (NullSentinel::Bool(_), DummyTypeSystem::Bool(_))
+            | (NullSentinel::String(_), DummyTypeSystem::String(_)) => {}
+            _ => throw!(ConnectorAgentError::TypeCheckFailed(
+                format!("{:?}", dt),
+                self.type_name()
+            )),
+        }
+    }
+
+    fn type_name(&self) -> &'static str {
+        match self {
+            NullSentinel::I64(_) => type_name::<i64>(),
+            NullSentinel::F64(_) => type_name::<f64>(),
+            NullSentinel::Bool(_) => type_name::<bool>(),
+            NullSentinel::String(_) => type_name::<String>(),
+        }
+    }
+
+    /// Replace the nulls in `array` with the sentinel. The returned array has no null bitmap.
+    #[throws(ConnectorAgentError)]
+    pub fn fill(&self, array: ArrayRef) -> ArrayRef {
+        match self {
+            NullSentinel::I64(s) => {
+                let arr = downcast::<Int64Array>(&array)?;
+                let values: Vec<i64> = (0..arr.len())
+                    .map(|i| if arr.is_null(i) { *s } else { arr.value(i) })
+                    .collect();
+                Arc::new(Int64Array::from(values)) as ArrayRef
+            }
+            NullSentinel::F64(s) => {
+                let arr = downcast::<Float64Array>(&array)?;
+                let values: Vec<f64> = (0..arr.len())
+                    .map(|i| if arr.is_null(i) { *s } else { arr.value(i) })
+                    .collect();
+                Arc::new(Float64Array::from(values)) as ArrayRef
+            }
+            NullSentinel::Bool(s) => {
+                let arr = downcast::<BooleanArray>(&array)?;
+                let values: Vec<bool> = (0..arr.len())
+                    .map(|i| if arr.is_null(i) { *s } else { arr.value(i) })
+                    .collect();
+                Arc::new(BooleanArray::from(values)) as ArrayRef
+            }
+            NullSentinel::String(s) => {
+                let arr = downcast::<StringArray>(&array)?;
+                let values: Vec<&str> = (0..arr.len())
+                    .map(|i| {
+                        if arr.is_null(i) {
+                            s.as_str()
+                        } else {
+                            arr.value(i)
+                        }
+                    })
+                    .collect();
+                Arc::new(StringArray::from(values)) as ArrayRef
+            }
+        }
+    }
+}
+
+fn downcast<T: 'static>(array: &ArrayRef) -> Result<&T> {
+    array
+        .as_any()
+        .downcast_ref::<T>()
+        .ok_or_else(|| anyhow!("cannot cast arrow array for null sentinel").into())
+}
@@ -0,0 +1,222 @@
+use crate::destinations::DecimalPrecision;
+use crate::dummy_typesystem::DummyTypeSystem;
+use crate::errors::{ConnectorAgentError, Result};
+use crate::point::Point;
+use crate::range::Range;
+use avro_rs::types::Value;
+use chrono::{Date, DateTime, NaiveDate, Utc};
+use fehler::throws;
+use rust_decimal::Decimal;
+use serde_json::{json, Value as JsonValue};
+
+/// Associate the avro value with native type
+pub trait AvroAssoc: Sized {
+    fn into_avro(self) -> Result<Value>;
+
+    /// Like `into_avro`, for a column whose decimals are written at `decimal`.
+    fn into_avro_at(self, _decimal: DecimalPrecision) -> Result<Value> {
+        self.into_avro()
+    }
+}
+
+/// The avro schema of a column of type `dt`, `index` makes the names of its records unique.
+/// Decimals are written at `decimal`.
+pub fn avro_type(index: usize, dt: DummyTypeSystem, decimal: DecimalPrecision) -> JsonValue {
+    use DummyTypeSystem::*;
+    let (ty, nullable) = match dt {
+        F64(nullable) => (json!("double"), nullable),
+        I64(nullable) => (json!("long"), nullable),
+        Bool(nullable) => (json!("boolean"), nullable),
+        String(nullable) => (json!("string"), nullable),
+        DateTime(nullable) => (
+            json!({"type": "long", "logicalType": "timestamp-micros"}),
+            nullable,
+        ),
+        Date(nullable) => (json!({"type": "int", "logicalType": "date"}), nullable),
+        Decimal(nullable) => (
+            json!({
+                "type": "bytes",
+                "logicalType": "decimal",
+                "precision": decimal.precision,
+                "scale": decimal.scale,
+            }),
+            nullable,
+        ),
+        Bytes(nullable) => (json!("bytes"), nullable),
+        I64Range(nullable) => (range_type(index, json!("long")), nullable),
+        DateRange(nullable) => (
+            range_type(index, json!({"type": "int", "logicalType": "date"})),
+            nullable,
+        ),
+        Point(nullable) => (
+            json!({
+                "type": "record",
+                "name": format!("point_{}", index),
+                "fields": [
+                    {"name": "x", "type": "double"},
+                    {"name": "y", "type": "double"},
+                ],
+            }),
+            nullable,
+        ),
+    };
+    match nullable {
+        true => json!(["null", ty]),
+        false => ty,
+    }
+}
+
+fn range_type(index: usize, bound: JsonValue) -> JsonValue {
+    json!({
+        "type": "record",
+        "name": format!("range_{}", index),
+        "fields": [
+            {"name": "lower", "type": ["null", bound]},
+            {"name": "upper", "type": ["null", bound]},
+            {"name": "lower_inc", "type": "boolean"},
+            {"name": "upper_inc", "type": "boolean"},
+            {"name": "empty", "type": "boolean"},
+        ],
+    })
+}
+
+fn null() -> Value {
+    Value::Union(Box::new(Value::Null))
+}
+
+fn some(value: Value) -> Value {
+    Value::Union(Box::new(value))
+}
+
+macro_rules! impl_avro_assoc {
+    ($($t: ty,)+) => {
+        $(
+            impl AvroAssoc for Option<$t> {
+                fn into_avro(self) -> Result<Value> {
+                    match self {
+                        Some(value) => Ok(some(value.into_avro()?)),
+                        None => Ok(null()),
+                    }
+                }
+
+                fn into_avro_at(self, decimal: DecimalPrecision) -> Result<Value> {
+                    match self {
+                        Some(value) => Ok(some(value.into_avro_at(decimal)?)),
+                        None => Ok(null()),
+                    }
+                }
+            }
+        )+
+    };
+}
+
+impl_avro_assoc!(
+    f64,
+    i64,
+    bool,
+    String,
+    DateTime<Utc>,
+    Date<Utc>,
+    Decimal,
+    Vec<u8>,
+    Range<i64>,
+    Range<NaiveDate>,
+    Point,
+);
+
+impl AvroAssoc for f64 {
+    #[throws(ConnectorAgentError)]
+    fn into_avro(self) -> Value {
+        Value::Double(self)
+    }
+}
+
+impl AvroAssoc for i64 {
+    #[throws(ConnectorAgentError)]
+    fn into_avro(self) -> Value {
+        Value::Long(self)
+    }
+}
+
+impl AvroAssoc for bool {
+    #[throws(ConnectorAgentError)]
+    fn into_avro(self) -> Value {
+        Value::Boolean(self)
+    }
+}
+
+impl AvroAssoc for String {
+    #[throws(ConnectorAgentError)]
+    fn into_avro(self) -> Value {
+        Value::String(self)
+    }
+}
+
+impl AvroAssoc for DateTime<Utc> {
+    #[throws(ConnectorAgentError)]
+    fn into_avro(self) -> Value {
+        let micros = self.timestamp() * 1_000_000 + self.timestamp_subsec_micros() as i64;
+        Value::TimestampMicros(micros)
+    }
+}
+
+/// The big-endian two's complement of the mantissa at the scale of the column.
+impl AvroAssoc for Decimal {
+    fn into_avro(self) -> Result<Value> {
+        self.into_avro_at(DecimalPrecision::default())
+    }
+
+    #[throws(ConnectorAgentError)]
+    fn into_avro_at(self, decimal: DecimalPrecision) -> Value {
+        Value::Decimal(decimal.mantissa(self)?.to_be_bytes().to_vec().into())
+    }
+}
+
+impl AvroAssoc for Vec<u8> {
+    #[throws(ConnectorAgentError)]
+    fn into_avro(self) -> Value {
+        Value::Bytes(self)
+    }
+}
+
+impl AvroAssoc for NaiveDate {
+    #[throws(ConnectorAgentError)]
+    fn into_avro(self) -> Value {
+        Value::Date((self - NaiveDate::from_ymd(1970, 1, 1)).num_days() as i32)
+    }
+}
+
+impl AvroAssoc for Date<Utc> {
+    fn into_avro(self) -> Result<Value> {
+        self.naive_utc().into_avro()
+    }
+}
+
+impl<T: AvroAssoc> AvroAssoc for Range<T> {
+    #[throws(ConnectorAgentError)]
+    fn into_avro(self) -> Value {
+        let bound = |bound: Option<T>| -> Result<Value> {
+            match bound {
+                Some(value) => Ok(some(value.into_avro()?)),
+                None => Ok(null()),
+            }
+        };
+        Value::Record(vec![
+            ("lower".to_string(), bound(self.lower)?),
+            ("upper".to_string(), bound(self.upper)?),
+            ("lower_inc".to_string(), Value::Boolean(self.lower_inc)),
+            ("upper_inc".to_string(), Value::Boolean(self.upper_inc)),
+            ("empty".to_string(), Value::Boolean(self.empty)),
+        ])
+    }
+}
+
+impl AvroAssoc for Point {
+    #[throws(ConnectorAgentError)]
+    fn into_avro(self) -> Value {
+        Value::Record(vec![
+            ("x".to_string(), Value::Double(self.x)),
+            ("y".to_string(), Value::Double(self.y)),
+        ])
+    }
+}
@@ -0,0 +1,257 @@
+use super::{Consume, DecimalPrecision, Destination, DestinationPartition};
+use crate::data_order::DataOrder;
+use crate::dummy_typesystem::DummyTypeSystem;
+use crate::errors::{ConnectorAgentError, Result};
+use crate::typesystem::{TypeAssoc, TypeSystem};
+use anyhow::anyhow;
+use avro_assoc::avro_type;
+use avro_rs::{types::Value, Schema, Writer};
+use fehler::{throw, throws};
+use serde_json::json;
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::mem;
+use std::sync::{Arc, Mutex};
+
+mod avro_assoc;
+
+pub use avro_assoc::AvroAssoc;
+
+/// The name of the avro record holding the columns of a row.
+pub const RECORD_NAME: &str = "connectorx";
+
+type Sink = Box<dyn Write + Send>;
+
+/// A destination writing the rows as avro records into an avro object container file.
+/// Each partition appends its records to the file as a block once it is read, so only the
+/// records of the partitions still being read are held in memory.
+pub struct AvroDestination {
+    names: Vec<String>,
+    schema: Vec<DummyTypeSystem>,
+    avro_schema: Option<Schema>,
+    decimals: HashMap<String, DecimalPrecision>,
+    column_decimals: Vec<DecimalPrecision>,
+    sink: Mutex<Sink>,
+}
+
+impl AvroDestination {
+    pub fn new<W: Write + Send + 'static>(sink: W) -> Self {
+        AvroDestination {
+            names: vec![],
+            schema: vec![],
+            avro_schema: None,
+            decimals: HashMap::new(),
+            column_decimals: vec![],
+            sink: Mutex::new(Box::new(sink)),
+        }
+    }
+
+    /// The avro schema of the records, available after allocation: a record named `RECORD_NAME`
+    /// with one field per column. Timestamps are `timestamp-micros` and decimals are `decimal`
+    /// at `DecimalPrecision::default()` unless set by `decimal_precision`. Nullable columns are
+    /// unions with `null`.
+    pub fn avro_schema(&self) -> Option<&Schema> {
+        self.avro_schema.as_ref()
+    }
+
+    /// Write the decimal `column` at `decimal` instead of `DecimalPrecision::default()`, e.g. at
+    /// the `NUMERIC(precision, scale)` it is declared with. Values that do not fit fail the write.
+    /// This needs to be set before allocation, where the column is checked to be a decimal.
+    pub fn decimal_precision(&mut self, column: &str, decimal: DecimalPrecision) {
+        self.decimals.insert(column.to_string(), decimal);
+    }
+
+    /// Flush the sink, to which the partitions have written their blocks as they were read.
+    #[throws(ConnectorAgentError)]
+    pub fn finish(self) {
+        self.sink
+            .into_inner()
+            .map_err(|_| anyhow!("avro sink is poisoned"))?
+            .flush()?;
+    }
+}
+
+impl Destination for AvroDestination {
+    const DATA_ORDERS: &'static [DataOrder] = &[DataOrder::RowMajor];
+    const GROWABLE: bool = true;
+    type TypeSystem = DummyTypeSystem;
+    type Partition<'a> = AvroPartitionWriter<'a>;
+
+    #[throws(ConnectorAgentError)]
+    fn allocate<S: AsRef<str>>(
+        &mut self,
+        _nrows: usize,
+        names: &[S],
+        schema: &[DummyTypeSystem],
+        _data_order: DataOrder,
+    ) {
+        let names: Vec<_> = names.iter().map(|n| n.as_ref().to_string()).collect();
+        for (column, decimal) in &self.decimals {
+            decimal.check()?;
+            match names.iter().position(|n| n == column) {
+                Some(i) if matches!(schema[i], DummyTypeSystem::Decimal(_)) => {}
+                Some(i) => throw!(ConnectorAgentError::TypeCheckFailed(
+                    format!("{:?}", schema[i]),
+                    std::any::type_name::<rust_decimal::Decimal>()
+                )),
+                None => throw!(anyhow!(
+                    "cannot set decimal precision for unknown column {}",
+                    column
+                )),
+            }
+        }
+        self.column_decimals = names
+            .iter()
+            .map(|n| self.decimals.get(n).cloned().unwrap_or_default())
+            .collect();
+        self.names = names.iter().map(|n| avro_name(n)).collect();
+        self.schema = schema.to_vec();
+
+        let fields: Vec<_> = self
+            .names
+            .iter()
+            .zip(&self.schema)
+            .zip(&self.column_decimals)
+            .enumerate()
+            .map(|(i, ((name, &dt), &decimal))| {
+                json!({"name": name, "type": avro_type(i, dt, decimal)})
+            })
+            .collect();
+        let avro_schema = json!({"type": "record", "name": RECORD_NAME, "fields": fields});
+        self.avro_schema = Some(Schema::parse(&avro_schema)?);
+    }
+
+    #[throws(ConnectorAgentError)]
+    fn partition(&mut self, counts: &[usize]) -> Vec<Self::Partition<'_>> {
+        let avro_schema = self
+            .avro_schema
+            .as_ref()
+            .ok_or_else(|| anyhow!("avro destination is not allocated"))?;
+        let writer = Arc::new(Mutex::new(Writer::new(avro_schema, SharedSink(&self.sink))));
+        counts
+            .iter()
+            .map(|&c| {
+                AvroPartitionWriter::new(
+                    self.names.clone(),
+                    self.schema.clone(),
+                    self.column_decimals.clone(),
+                    Arc::clone(&writer),
+                    c,
+                )
+            })
+            .collect()
+    }
+
+    fn schema(&self) -> &[DummyTypeSystem] {
+        self.schema.as_slice()
+    }
+}
+
+/// The sink of a destination, written by whichever partition holds the avro writer.
+pub struct SharedSink<'a>(&'a Mutex<Sink>);
+
+impl<'a> Write for SharedSink<'a> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .lock()
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "avro sink is poisoned"))?
+            .write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0
+            .lock()
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "avro sink is poisoned"))?
+            .flush()
+    }
+}
+
+/// Replace the characters avro does not allow in names with `_`.
+fn avro_name(name: &str) -> String {
+    let mut ret: String = name
+        .chars()
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c,
+            false => '_',
+        })
+        .collect();
+    if !ret.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        ret.insert(0, '_');
+    }
+    ret
+}
+
+pub struct AvroPartitionWriter<'a> {
+    nrows: usize,
+    names: Vec<String>,
+    schema: Vec<DummyTypeSystem>,
+    decimals: Vec<DecimalPrecision>,
+    writer: Arc<Mutex<Writer<'a, SharedSink<'a>>>>,
+    records: Vec<Value>,
+    current: Vec<(String, Value)>,
+}
+
+impl<'a> AvroPartitionWriter<'a> {
+    fn new(
+        names: Vec<String>,
+        schema: Vec<DummyTypeSystem>,
+        decimals: Vec<DecimalPrecision>,
+        writer: Arc<Mutex<Writer<'a, SharedSink<'a>>>>,
+        nrows: usize,
+    ) -> Self {
+        AvroPartitionWriter {
+            nrows,
+            current: Vec::with_capacity(schema.len()),
+            records: Vec::with_capacity(nrows),
+            names,
+            schema,
+            decimals,
+            writer,
+        }
+    }
+}
+
+impl<'a> DestinationPartition<'a> for AvroPartitionWriter<'a> {
+    type TypeSystem = DummyTypeSystem;
+
+    fn nrows(&self) -> usize {
+        self.nrows
+    }
+
+    fn ncols(&self) -> usize {
+        self.schema.len()
+    }
+
+    /// Append the records of the partition to the file and flush them as a block.
+    #[throws(ConnectorAgentError)]
+    fn finalize(&mut self) {
+        let records = mem::take(&mut self.records);
+        let mut writer = self
+            .writer
+            .lock()
+            .map_err(|_| anyhow!("avro writer is poisoned"))?;
+        writer.extend(records)?;
+        writer.flush()?;
+    }
+}
+
+impl<'a, T> Consume<T> for AvroPartitionWriter<'a>
+where
+    T: TypeAssoc<<Self as DestinationPartition<'a>>::TypeSystem> + AvroAssoc + 'static,
+{
+    fn consume(&mut self, value: T) -> Result<()> {
+        let col = self.current.len();
+        self.schema[col].check::<T>()?;
+
+        self.current.push((
+            self.names[col].clone(),
+            value.into_avro_at(self.decimals[col])?,
+        ));
+        let ncols = self.ncols();
+        if self.current.len() == ncols {
+            let record = mem::replace(&mut self.current, Vec::with_capacity(ncols));
+            self.records.push(Value::Record(record));
+        }
+        Ok(())
+    }
+}
@@ -1,16 +1,19 @@
 mod any_array;
 
-use super::{Consume, Destination, DestinationPartition};
+use super::{Consume, Destination, DestinationPartition, NonFinitePolicy};
 use crate::data_order::DataOrder;
 use crate::dummy_typesystem::DummyTypeSystem;
 use crate::errors::{ConnectorAgentError, Result};
+use crate::point::Point;
+use crate::range::Range;
 use crate::typesystem::{ParameterizedFunc, ParameterizedOn, Realize, TypeAssoc, TypeSystem};
 use any_array::{AnyArray, AnyArrayViewMut};
 use anyhow::anyhow;
-use chrono::{DateTime, NaiveDateTime, Utc};
+use chrono::{Date, DateTime, NaiveDate, NaiveDateTime, Utc};
 use fehler::{throw, throws};
 use itertools::Itertools;
 use ndarray::{Array2, ArrayView1, ArrayView2, Axis, Ix2};
+use rust_decimal::Decimal;
 use std::any::type_name;
 use std::collections::HashMap;
 /// This `Destination` can support mixed data type.
@@ -19,6 +22,7 @@ pub struct MemoryDestination {
     schema: Vec<DummyTypeSystem>,
     buffers: Vec<AnyArray<Ix2>>,
     column_buffer_index: Vec<(usize, usize)>,
+    non_finite: NonFinitePolicy,
 }
 
 impl MemoryDestination {
@@ -28,8 +32,15 @@ impl MemoryDestination {
             schema: vec![],
             buffers: vec![],
             column_buffer_index: vec![],
+            non_finite: NonFinitePolicy::Null,
         }
     }
+
+    /// Set how non-finite floats are written, defaults to `NonFinitePolicy::Null` since
+    /// a `NaN` in an ndarray is ambiguous with a missing value.
+    pub fn non_finite_policy(&mut self, policy: NonFinitePolicy) {
+        self.non_finite = policy;
+    }
 }
 
 impl Destination for MemoryDestination {
@@ -105,6 +116,7 @@ impl Destination for MemoryDestination {
                 sub_buffers,
                 self.schema.clone(),
                 self.column_buffer_index.clone(),
+                self.non_finite,
             ));
         }
         ret
@@ -144,6 +156,7 @@ pub struct MemoryPartitionDestination<'a> {
     schema: Vec<DummyTypeSystem>,
     buffers: Vec<AnyArrayViewMut<'a, Ix2>>,
     column_buffer_index: Vec<(usize, usize)>,
+    non_finite: NonFinitePolicy,
     current: usize,
 }
 
@@ -153,12 +166,14 @@ impl<'a> MemoryPartitionDestination<'a> {
         buffers: Vec<AnyArrayViewMut<'a, Ix2>>,
         schema: Vec<DummyTypeSystem>,
         column_buffer_index: Vec<(usize, usize)>,
+        non_finite: NonFinitePolicy,
     ) -> Self {
         Self {
             nrows,
             buffers,
             schema,
             column_buffer_index,
+            non_finite,
             current: 0,
         }
     }
@@ -180,6 +195,51 @@ impl<'a> DestinationPartition<'a> for MemoryPartitionDestination<'a> {
     fn ncols(&self) -> usize {
         self.schema.len()
     }
+
+    fn finalize(&mut self) -> Result<()> {
+        if matches!(self.non_finite, NonFinitePolicy::Preserve) {
+            return Ok(());
+        }
+
+        for (col, &dt) in self.schema.iter().enumerate() {
+            let (bid, sid) = self.column_buffer_index[col];
+            match dt {
+                DummyTypeSystem::F64(false) => {
+                    let view = self.buffers[bid].downcast::<f64>().ok_or_else(|| {
+                        ConnectorAgentError::TypeCheckFailed(
+                            format!("{:?}", dt),
+                            type_name::<f64>(),
+                        )
+                    })?;
+                    for v in view.column_mut(sid).iter_mut().filter(|v| !v.is_finite()) {
+                        match self.non_finite {
+                            NonFinitePolicy::Null => *v = f64::NAN,
+                            _ => throw!(ConnectorAgentError::NonFiniteFloat(*v)),
+                        }
+                    }
+                }
+                DummyTypeSystem::F64(true) => {
+                    let view = self.buffers[bid].downcast::<Option<f64>>().ok_or_else(|| {
+                        ConnectorAgentError::TypeCheckFailed(
+                            format!("{:?}", dt),
+                            type_name::<Option<f64>>(),
+                        )
+                    })?;
+                    for v in view.column_mut(sid).iter_mut() {
+                        match *v {
+                            Some(f) if !f.is_finite() => match self.non_finite {
+                                NonFinitePolicy::Null => *v = None,
+                                _ => throw!(ConnectorAgentError::NonFiniteFloat(f)),
+                            },
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
 }
 
 impl<'a, T> Consume<T> for MemoryPartitionDestination<'a>
@@ -227,11 +287,21 @@ FArray2Parameterize!(
     f64,
     String,
     bool,
+    Decimal,
+    Vec<u8>,
+    Range<i64>,
+    Range<NaiveDate>,
+    Point,
     Option<i32>,
     Option<i64>,
     Option<f64>,
     Option<String>,
-    Option<bool>
+    Option<bool>,
+    Option<Decimal>,
+    Option<Vec<u8>>,
+    Option<Range<i64>>,
+    Option<Range<NaiveDate>>,
+    Option<Point>
 );
 
 fn create_default_array<T>(nrows: usize, ncols: usize) -> AnyArray<Ix2>
@@ -259,3 +329,22 @@ impl ParameterizedOn<Option<DateTime<Utc>>> for FArray2 {
         imp
     }
 }
+
+impl ParameterizedOn<Date<Utc>> for FArray2 {
+    fn parameterize() -> Self::Function {
+        fn imp(nrows: usize, ncols: usize) -> AnyArray<Ix2> {
+            let t = Date::<Utc>::from_utc(NaiveDate::from_ymd(1970, 1, 1), Utc);
+            Array2::from_elem((nrows, ncols), t).into()
+        }
+        imp
+    }
+}
+
+impl ParameterizedOn<Option<Date<Utc>>> for FArray2 {
+    fn parameterize() -> Self::Function {
+        fn imp(nrows: usize, ncols: usize) -> AnyArray<Ix2> {
+            Array2::<Option<Date<Utc>>>::from_elem((nrows, ncols), None).into()
+        }
+        imp
+    }
+}
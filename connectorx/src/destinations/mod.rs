@@ -1,14 +1,22 @@
 pub mod arrow;
+#[cfg(feature = "avro")]
+pub mod avro;
 pub mod memory;
 
 use crate::data_order::DataOrder;
-use crate::errors::Result;
+use crate::errors::{ConnectorAgentError, Result};
 use crate::typesystem::{TypeAssoc, TypeSystem};
+use anyhow::anyhow;
+use fehler::{throw, throws};
+use rust_decimal::Decimal;
 
 /// A `Destination` is associated with a `TypeSystem` and a `PartitionDestination`.
 /// `PartitionDestination` allows multiple threads write data into the buffer owned by `Destination`.
 pub trait Destination: Sized {
     const DATA_ORDERS: &'static [DataOrder];
+    /// Whether the partitions grow with the rows written to them, so that they can be created
+    /// without knowing their row counts. Otherwise `partition` fixes the size of each partition.
+    const GROWABLE: bool = false;
     type TypeSystem: TypeSystem;
     type Partition<'a>: DestinationPartition<'a, TypeSystem = Self::TypeSystem>;
 
@@ -57,6 +65,77 @@ pub trait DestinationPartition<'a>: Send {
     }
 }
 
+/// How a destination writes non-finite floats (`inf`, `-inf` and `NaN`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NonFinitePolicy {
+    /// Write the value as is.
+    Preserve,
+    /// Write null instead. For non-nullable buffers that cannot hold a null, `NaN` is written.
+    Null,
+    /// Fail with `ConnectorAgentError::NonFiniteFloat`.
+    Error,
+}
+
+/// The precision and scale a destination writes a decimal column with, which the source cannot
+/// tell: `DummyTypeSystem::Decimal` does not carry the precision and scale of the column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecimalPrecision {
+    pub precision: usize,
+    pub scale: usize,
+}
+
+impl Default for DecimalPrecision {
+    /// The widest precision an `i128` holds, with 10 fractional digits.
+    fn default() -> Self {
+        DecimalPrecision {
+            precision: 38,
+            scale: 10,
+        }
+    }
+}
+
+impl DecimalPrecision {
+    /// Check that the precision is between 1 and 38 and the scale does not exceed it.
+    #[throws(ConnectorAgentError)]
+    pub fn check(&self) {
+        if self.precision == 0 || self.precision > 38 || self.scale > self.precision {
+            throw!(anyhow!(
+                "invalid decimal precision {} and scale {}",
+                self.precision,
+                self.scale
+            ));
+        }
+    }
+
+    /// The mantissa of `value` at `scale`. Fails instead of rounding when `value` has non-zero
+    /// digits beyond `scale`, or more digits than `precision`.
+    #[throws(ConnectorAgentError)]
+    pub fn mantissa(&self, value: Decimal) -> i128 {
+        let (mantissa, scale) = (value.mantissa(), value.scale() as usize);
+        let mantissa = match scale <= self.scale {
+            true => 10i128
+                .checked_pow((self.scale - scale) as u32)
+                .and_then(|m| mantissa.checked_mul(m)),
+            false => {
+                let div = 10i128.pow((scale - self.scale) as u32);
+                match mantissa % div {
+                    0 => Some(mantissa / div),
+                    _ => None,
+                }
+            }
+        };
+        match mantissa {
+            Some(m) if m.unsigned_abs() < 10u128.pow(self.precision as u32) => m,
+            _ => throw!(anyhow!(
+                "decimal {} does not fit a precision of {} and a scale of {}",
+                value,
+                self.precision,
+                self.scale
+            )),
+        }
+    }
+}
+
 /// A type implemented `Consume<T>` means that it can consume a value `T` by adding it to it's own buffer.
 pub trait Consume<T> {
     fn consume(&mut self, value: T) -> Result<()>;
@@ -1,10 +1,11 @@
 use crate::{
     data_order::{coordinate, DataOrder},
     destinations::{Destination, DestinationPartition},
-    errors::Result,
-    sources::{Source, SourcePartition},
+    errors::{ConnectorAgentError, Result},
+    sources::{PartitionParser, Source, SourcePartition},
     typesystem::{Transport, TypeSystem},
 };
+use fehler::throw;
 use itertools::Itertools;
 use log::debug;
 use rayon::prelude::*;
@@ -16,6 +17,7 @@ pub struct Dispatcher<'a, S, W, TP> {
     src: S,
     dst: &'a mut W,
     queries: Vec<String>,
+    skip_empty_partitions: bool,
     _phantom: PhantomData<TP>,
 }
 
@@ -37,10 +39,19 @@ where
             src,
             dst,
             queries: queries.into_iter().map(ToString::to_string).collect(),
+            skip_empty_partitions: false,
             _phantom: PhantomData,
         }
     }
 
+    /// Drop the partitions that yield no rows before reading them, so that no query is issued
+    /// for an empty bucket (e.g. a sparse key range). The row counts come from
+    /// `SourcePartition::prepare`, which runs an exact count instead of an estimate.
+    /// If every partition is empty, all of them are kept so the destination still gets allocated.
+    pub fn skip_empty_partitions(&mut self, skip: bool) {
+        self.skip_empty_partitions = skip;
+    }
+
     /// Run the dispatcher by specifying the src, the dispatcher will fetch, parse the data,
     /// and write the data to dst.
     pub fn run(mut self) -> Result<()> {
@@ -55,6 +66,7 @@ where
             .map(|&s| TP::convert_typesystem(s))
             .collect::<Result<Vec<_>>>()?;
         let names = self.src.names();
+        let expected_nrows = self.src.expected_nrows();
 
         // generate partitions
         let mut src_partitions: Vec<S::Partition> = self.src.partition()?;
@@ -64,6 +76,24 @@ where
             .par_iter_mut()
             .try_for_each(|partition| -> Result<()> { partition.prepare() })?;
 
+        // partitions without an exact count may still yield rows
+        let maybe_rows = |p: &S::Partition| p.nrows() > 0 || !p.nrows_exact();
+        if self.skip_empty_partitions && src_partitions.iter().any(maybe_rows) {
+            let total = src_partitions.len();
+            src_partitions.retain(maybe_rows);
+            debug!("Skip {} empty partitions", total - src_partitions.len());
+        }
+
+        if src_partitions.iter().any(|p| !p.nrows_exact()) {
+            if !W::GROWABLE {
+                throw!(ConnectorAgentError::RowCountRequired);
+            }
+            if matches!(dorder, DataOrder::ColumnMajor) {
+                // a column can only be filled up to a known number of rows
+                throw!(ConnectorAgentError::UnsupportedDataOrder(dorder));
+            }
+        }
+
         // allocate memory and create one partition for each source
         let num_rows: Vec<usize> = src_partitions
             .iter()
@@ -93,11 +123,11 @@ where
 
         debug!("Start writing");
         // parse and write
-        dst_partitions
+        let nread: Vec<usize> = dst_partitions
             .into_par_iter()
             .zip_eq(src_partitions)
             .enumerate()
-            .try_for_each(|(i, (mut src, mut dst))| -> Result<()> {
+            .map(|(i, (mut src, mut dst))| -> Result<usize> {
                 #[cfg(feature = "fptr")]
                 let f: Vec<_> = src_schema
                     .iter()
@@ -105,23 +135,33 @@ where
                     .map(|(&src_ty, &dst_ty)| TP::processor(src_ty, dst_ty))
                     .collect::<Result<Vec<_>>>()?;
 
+                let nrows_exact = dst.nrows_exact();
                 let mut parser = dst.parser()?;
 
+                let mut nread = 0;
                 match dorder {
-                    DataOrder::RowMajor => {
-                        for _ in 0..src.nrows() {
-                            for col in 0..src.ncols() {
-                                #[cfg(feature = "fptr")]
-                                f[col](&mut parser, &mut src)?;
+                    DataOrder::RowMajor => loop {
+                        let finished = if nrows_exact {
+                            nread >= src.nrows()
+                        } else {
+                            parser.is_finished()?
+                        };
+                        if finished {
+                            break;
+                        }
 
-                                #[cfg(feature = "branch")]
-                                {
-                                    let (s1, s2) = schemas[col];
-                                    TP::process(s1, s2, &mut parser, &mut src)?;
-                                }
+                        for col in 0..src.ncols() {
+                            #[cfg(feature = "fptr")]
+                            f[col](&mut parser, &mut src)?;
+
+                            #[cfg(feature = "branch")]
+                            {
+                                let (s1, s2) = schemas[col];
+                                TP::process(s1, s2, &mut parser, &mut src)?;
                             }
                         }
-                    }
+                        nread += 1;
+                    },
                     DataOrder::ColumnMajor => {
                         for col in 0..src.ncols() {
                             for _ in 0..src.nrows() {
@@ -134,17 +174,69 @@ where
                                 }
                             }
                         }
+                        nread = src.nrows();
                     }
                 }
 
                 debug!("Finalize partition {}", i);
                 src.finalize()?;
                 debug!("Partition {} finished", i);
-                Ok(())
-            })?;
+                Ok(nread)
+            })
+            .collect::<Result<_>>()?;
 
         debug!("Writing finished");
 
+        if let Some(expected) = expected_nrows {
+            let total: usize = nread.iter().sum();
+            if total != expected {
+                throw!(ConnectorAgentError::RowCountMismatch(expected, total));
+            }
+        }
+
         Ok(())
     }
+
+    /// Only fetch the metadata of the queries and allocate the destination with zero rows,
+    /// so that the destination holds the resolved schema without any data being read.
+    pub fn get_meta(mut self) -> Result<()> {
+        let dorder = coordinate(S::DATA_ORDERS, W::DATA_ORDERS)?;
+        self.src.set_data_order(dorder)?;
+        self.src.set_queries(self.queries.as_slice());
+        debug!("Fetching metadata");
+        self.src.fetch_metadata()?;
+        let dst_schema = self
+            .src
+            .schema()
+            .iter()
+            .map(|&s| TP::convert_typesystem(s))
+            .collect::<Result<Vec<_>>>()?;
+        let names = self.src.names();
+
+        debug!("Allocate empty destination");
+        self.dst.allocate(0, &names, &dst_schema, dorder)?;
+        Ok(())
+    }
+}
+
+/// Run `read` again, at most `retries` more times, when it fails because the schema changed
+/// during the read (see `ConnectorAgentError::is_schema_change`). `read` should create the source
+/// and the destination anew, so that the metadata is fetched and the queries partitioned again.
+pub fn retry_on_schema_change<T, F>(retries: usize, mut read: F) -> Result<T>
+where
+    F: FnMut() -> Result<T>,
+{
+    let mut attempt = 0;
+    loop {
+        match read() {
+            Err(e) if attempt < retries && e.is_schema_change() => {
+                attempt += 1;
+                debug!(
+                    "Schema changed during the read, retry {}/{}: {}",
+                    attempt, retries, e
+                );
+            }
+            res => return res,
+        }
+    }
 }
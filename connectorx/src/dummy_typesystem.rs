@@ -6,7 +6,11 @@
 // 3. Add `DataType::T => N` to the macro impl_transmit!.
 //
 
-use chrono::{DateTime, Utc};
+use crate::point::Point;
+use crate::range::Range;
+use chrono::{Date, DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+
 /// This is a dummy type system used in this library.
 /// For all the sources, their output values must be one of the types defined by DummyTypeSystem.
 /// For all the destinations, they must support writing any value whose type is defined by DummyTypeSystem.
@@ -17,6 +21,12 @@ pub enum DummyTypeSystem {
     Bool(bool),
     String(bool),
     DateTime(bool),
+    Date(bool),
+    Decimal(bool),
+    Bytes(bool),
+    I64Range(bool),
+    DateRange(bool),
+    Point(bool),
 }
 
 impl_typesystem! {
@@ -27,5 +37,11 @@ impl_typesystem! {
         { Bool => bool }
         { String => String }
         { DateTime => DateTime<Utc> }
+        { Date => Date<Utc> }
+        { Decimal => Decimal }
+        { Bytes => Vec<u8> }
+        { I64Range => Range<i64> }
+        { DateRange => Range<NaiveDate> }
+        { Point => Point }
     }
 }
@@ -30,6 +30,15 @@ pub enum ConnectorAgentError {
     #[error("Destination has not been allocated yet.")]
     DestinationNotAllocated,
 
+    #[error("The destination needs the row count of every partition, the source has none.")]
+    RowCountRequired,
+
+    #[error("Non-finite float {0} found.")]
+    NonFiniteFloat(f64),
+
+    #[error("Timestamp {0} cannot be represented in nanoseconds.")]
+    TimestampOutOfRange(chrono::DateTime<chrono::Utc>),
+
     #[error("No conversion rule from {0} to {1}.")]
     NoConversionRule(String, String),
 
@@ -39,6 +48,24 @@ pub enum ConnectorAgentError {
     #[error("Only support partition on SPJ query, got {0}.")]
     SQLQueryPartitionNotSupported(String),
 
+    #[error("OFFSET partition requires a deterministic ORDER BY, got {0}.")]
+    SQLQueryNotOrdered(String),
+
+    #[error("Row count {0} cannot be represented as usize on this platform.")]
+    CountOverflow(String),
+
+    #[error("Timed out after {0:?} waiting for a connection: {1}")]
+    ConnectionTimeout(std::time::Duration, String),
+
+    #[error("Read {1} rows, but the query counts {0}.")]
+    RowCountMismatch(usize, usize),
+
+    #[error("The metadata has {0} columns, but the query returns {1}.")]
+    ColumnCountMismatch(usize, usize),
+
+    #[error("More than {0} rows could not be read, last error: {1}")]
+    TooManyDeadLetters(usize, String),
+
     #[error(transparent)]
     IOError(#[from] std::io::Error),
 
@@ -66,6 +93,14 @@ pub enum ConnectorAgentError {
     #[error(transparent)]
     ArrowError(#[from] arrow::error::ArrowError),
 
+    #[cfg(feature = "datafusion")]
+    #[error(transparent)]
+    DataFusionError(#[from] datafusion::error::DataFusionError),
+
+    #[cfg(feature = "avro")]
+    #[error(transparent)]
+    AvroError(#[from] avro_rs::Error),
+
     #[error(transparent)]
     HexError(#[from] hex::FromHexError),
 
@@ -78,6 +113,27 @@ impl ConnectorAgentError {
     pub fn cannot_produce<T>(context: Option<String>) -> Self {
         ConnectorAgentError::CannotProduce(type_name::<T>(), context.into())
     }
+
+    /// Whether the error is reported by the database or the source because the schema of the
+    /// queried tables changed after the metadata was fetched, e.g. a column added or dropped by
+    /// a concurrent migration. Errors from values not matching the schema are not included.
+    pub fn is_schema_change(&self) -> bool {
+        use postgres::error::SqlState;
+        match self {
+            ConnectorAgentError::ColumnCountMismatch(..) => true,
+            ConnectorAgentError::PostgresError(e) => match e.as_db_error() {
+                Some(e) => {
+                    e.code() == &SqlState::FEATURE_NOT_SUPPORTED
+                        && e.message() == "cached plan must not change result type"
+                }
+                None => false,
+            },
+            ConnectorAgentError::SQLiteError(rusqlite::Error::SqliteFailure(e, _)) => {
+                e.code == rusqlite::ErrorCode::SchemaChanged
+            }
+            _ => false,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -9,11 +9,14 @@ pub mod s3;
 pub mod typesystem;
 #[macro_use]
 pub mod macros;
+pub mod cache;
 pub mod data_order;
 pub mod destinations;
 pub mod dispatcher;
 pub mod dummy_typesystem;
 pub mod errors;
+pub mod point;
+pub mod range;
 pub mod source_router;
 pub mod sources;
 pub mod sql;
@@ -0,0 +1,41 @@
+use crate::errors::{ConnectorAgentError, Result};
+use anyhow::anyhow;
+use std::str::FromStr;
+
+/// A point of the plane, e.g. a Postgres `point`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Point {
+    pub fn new(x: f64, y: f64) -> Self {
+        Point { x, y }
+    }
+}
+
+/// Parse the text representation of Postgres points, e.g. `(1,2.5)`.
+impl FromStr for Point {
+    type Err = ConnectorAgentError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let coords = s
+            .strip_prefix('(')
+            .and_then(|s| s.strip_suffix(')'))
+            .ok_or_else(|| anyhow!("point {} is not in parentheses", s))?;
+        let comma = coords
+            .find(',')
+            .ok_or_else(|| anyhow!("point {} has no comma", s))?;
+        let coord = |c: &str| -> Result<f64> {
+            c.trim()
+                .parse()
+                .map_err(|_| anyhow!("cannot parse point coordinate {}", c).into())
+        };
+
+        Ok(Point::new(
+            coord(&coords[..comma])?,
+            coord(&coords[comma + 1..])?,
+        ))
+    }
+}
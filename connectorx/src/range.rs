@@ -0,0 +1,112 @@
+use crate::errors::{ConnectorAgentError, Result};
+use anyhow::anyhow;
+use fehler::throw;
+use std::str::FromStr;
+
+/// A range of values, e.g. a Postgres `int4range` or `daterange`.
+/// A missing bound means the range is unbounded on that side. `empty` marks a range
+/// containing no value, in which case there are no bounds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Range<T> {
+    pub lower: Option<T>,
+    pub upper: Option<T>,
+    pub lower_inc: bool,
+    pub upper_inc: bool,
+    pub empty: bool,
+}
+
+impl<T> Range<T> {
+    pub fn new(lower: Option<T>, upper: Option<T>, lower_inc: bool, upper_inc: bool) -> Self {
+        Range {
+            lower,
+            upper,
+            lower_inc,
+            upper_inc,
+            empty: false,
+        }
+    }
+
+    pub fn empty() -> Self {
+        Range {
+            lower: None,
+            upper: None,
+            lower_inc: false,
+            upper_inc: false,
+            empty: true,
+        }
+    }
+
+    pub fn map<U, F>(self, f: F) -> Range<U>
+    where
+        F: Fn(T) -> U,
+    {
+        Range {
+            lower: self.lower.map(&f),
+            upper: self.upper.map(&f),
+            lower_inc: self.lower_inc,
+            upper_inc: self.upper_inc,
+            empty: self.empty,
+        }
+    }
+}
+
+impl<T> Default for Range<T> {
+    fn default() -> Self {
+        Range::empty()
+    }
+}
+
+/// Parse the text representation of Postgres ranges, e.g. `[1,10)`, `(,2020-01-01]` or `empty`.
+impl<T: FromStr> FromStr for Range<T> {
+    type Err = ConnectorAgentError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if s == "empty" {
+            return Ok(Range::empty());
+        }
+
+        let lower_inc = match s.chars().next() {
+            Some('[') => true,
+            Some('(') => false,
+            _ => throw!(anyhow!("range {} does not start with a bracket", s)),
+        };
+        let upper_inc = match s.chars().last() {
+            Some(']') => true,
+            Some(')') => false,
+            _ => throw!(anyhow!("range {} does not end with a bracket", s)),
+        };
+        let bounds = &s[1..s.len() - 1];
+        let comma = find_separator(bounds).ok_or_else(|| anyhow!("range {} has no comma", s))?;
+
+        Ok(Range::new(
+            parse_bound(&bounds[..comma])?,
+            parse_bound(&bounds[comma + 1..])?,
+            lower_inc,
+            upper_inc,
+        ))
+    }
+}
+
+/// The position of the comma between the bounds, skipping the ones in quoted bounds.
+fn find_separator(bounds: &str) -> Option<usize> {
+    let mut quoted = false;
+    for (i, c) in bounds.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ',' if !quoted => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn parse_bound<T: FromStr>(bound: &str) -> Result<Option<T>> {
+    let bound = bound.trim_matches('"');
+    if bound.is_empty() {
+        return Ok(None);
+    }
+    bound
+        .parse()
+        .map(Some)
+        .map_err(|_| anyhow!("cannot parse range bound {}", bound).into())
+}
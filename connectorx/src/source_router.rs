@@ -1,9 +1,15 @@
+use crate::cache::QueryCache;
+use crate::destinations::arrow::ArrowDestination;
+use crate::dispatcher::Dispatcher;
 use crate::errors::{ConnectorAgentError, Result};
-use crate::sources::postgres::PostgresTypeSystem;
+use crate::sources::postgres::{Binary, PostgresSource, PostgresTypeSystem};
 use crate::sql::{
-    get_partition_range_query, get_partition_range_query_sep, single_col_partition_query,
+    count_query, get_partition_range_query, get_partition_range_query_sep, hash_partition_query,
+    offset_partition_query, single_col_partition_query, watermark_max_query, watermark_query,
 };
+use crate::transports::PostgresArrowTransport;
 use anyhow::anyhow;
+use arrow::record_batch::RecordBatch;
 use fehler::{throw, throws};
 use postgres::{Client, NoTls};
 use rusqlite::{types::Type, Connection};
@@ -40,6 +46,86 @@ impl TryFrom<&str> for SourceConn {
     }
 }
 
+impl SourceConn {
+    /// A batch without rows carrying the arrow schema the queries resolve to, for consumers
+    /// creating the tables ahead. Only the metadata of the queries is fetched.
+    #[throws(ConnectorAgentError)]
+    pub fn empty_batch<Q: AsRef<str>>(&self, queries: &[Q]) -> RecordBatch {
+        let queries: Vec<&str> = queries.iter().map(|q| q.as_ref()).collect();
+        match self.ty {
+            SourceType::Postgres => {
+                let source = PostgresSource::<Binary>::new(&self.conn, 1)?;
+                let mut destination = ArrowDestination::new();
+                let dispatcher = Dispatcher::<_, _, PostgresArrowTransport>::new(
+                    source,
+                    &mut destination,
+                    &queries,
+                );
+                dispatcher.get_meta()?;
+                destination.empty_batch()?
+            }
+            SourceType::Sqlite => throw!(anyhow!("Arrow is not supported on sqlite")),
+        }
+    }
+
+    /// Read `queries` into arrow record batches, one per query, each query being a partition.
+    /// With `cache`, the batches of a previous read of the same queries are returned if cached.
+    #[throws(ConnectorAgentError)]
+    pub fn read_arrow<Q: AsRef<str>>(
+        &self,
+        queries: &[Q],
+        cache: Option<&mut QueryCache>,
+    ) -> Vec<RecordBatch> {
+        let queries: Vec<&str> = queries.iter().map(|q| q.as_ref()).collect();
+        let read = || -> Result<Vec<RecordBatch>> {
+            match self.ty {
+                SourceType::Postgres => {
+                    let source = PostgresSource::<Binary>::new(&self.conn, queries.len())?;
+                    let mut destination = ArrowDestination::new();
+                    let dispatcher = Dispatcher::<_, _, PostgresArrowTransport>::new(
+                        source,
+                        &mut destination,
+                        &queries,
+                    );
+                    dispatcher.run()?;
+                    let names = destination.names().to_vec();
+                    destination.finish(names)
+                }
+                SourceType::Sqlite => throw!(anyhow!("Arrow is not supported on sqlite")),
+            }
+        };
+        match cache {
+            Some(cache) => cache.get_or_read(&self.conn, &queries, &[], read)?,
+            None => read()?,
+        }
+    }
+
+    /// Read the rows of `query` whose watermark column `col` is after `last` into arrow record
+    /// batches. Returns the batches together with the new watermark, the maximum of `col` over
+    /// the rows read, to pass as `last` on the next run. Without new rows, no batches are
+    /// returned and the watermark stays `last`.
+    #[throws(ConnectorAgentError)]
+    pub fn read_arrow_incremental(
+        &self,
+        query: &str,
+        col: &str,
+        last: Option<&str>,
+    ) -> (Vec<RecordBatch>, Option<String>) {
+        if let SourceType::Sqlite = self.ty {
+            throw!(anyhow!("Arrow is not supported on sqlite"));
+        }
+        match self
+            .ty
+            .get_incremental_part_query(&self.conn, query, col, last)?
+        {
+            Some((part_query, watermark)) => {
+                (self.read_arrow(&[part_query], None)?, Some(watermark))
+            }
+            None => (vec![], last.map(|last| last.to_string())),
+        }
+    }
+}
+
 impl SourceType {
     pub fn get_col_range(&self, conn: &str, query: &str, col: &str) -> Result<(i64, i64)> {
         match *self {
@@ -48,6 +134,83 @@ impl SourceType {
         }
     }
 
+    /// Split `query` into `num` queries on the integer column `col`, whose bounds are spread evenly
+    /// over `range`, or over the range of `col` probed from the database if it is not given.
+    /// Rows with a NULL in `col` are not read by any of the queries.
+    #[throws(ConnectorAgentError)]
+    pub fn get_part_queries(
+        &self,
+        conn: &str,
+        query: &str,
+        col: &str,
+        range: Option<(i64, i64)>,
+        num: usize,
+    ) -> Vec<String> {
+        let (min, max) = match range {
+            Some(range) => range,
+            None => self.get_col_range(conn, query, col)?,
+        };
+
+        partition_ranges(min, max, num)?
+            .into_iter()
+            .map(|(lower, upper)| self.get_part_query(query, col, lower, upper))
+            .collect::<Result<Vec<_>>>()?
+    }
+
+    /// Split `query` into `num` queries by hashing the column `col`, for keys that are not integers.
+    /// Rows with a NULL in `col` are not read by any of the queries.
+    #[throws(ConnectorAgentError)]
+    pub fn get_hash_part_queries(&self, query: &str, col: &str, num: usize) -> Vec<String> {
+        if num == 0 {
+            throw!(anyhow!("Number of partitions should be positive"));
+        }
+        match *self {
+            SourceType::Postgres => (0..num)
+                .map(|k| {
+                    hash_partition_query(query, col, "hashtext", num, k, &PostgreSqlDialect {})
+                })
+                .collect::<Result<Vec<_>>>()?,
+            SourceType::Sqlite => throw!(anyhow!(
+                "Hash partition is not supported on sqlite, which has no hash function"
+            )),
+        }
+    }
+
+    /// Split `query` into `num` queries reading consecutive slices of its rows with LIMIT and OFFSET,
+    /// for queries without a column to partition on. The rows are counted first.
+    /// The query must have an ORDER BY on keys that are unique: every query sorts the rows on its own,
+    /// so rows tying on the keys may come in a different order each time and be read twice or missed.
+    /// Rows inserted or deleted in between the queries shift the slices as well.
+    #[throws(ConnectorAgentError)]
+    pub fn get_offset_part_queries(&self, conn: &str, query: &str, num: usize) -> Vec<String> {
+        if num == 0 {
+            throw!(anyhow!("Number of partitions should be positive"));
+        }
+        let count = match *self {
+            SourceType::Postgres => pg_get_count(conn, query)?,
+            SourceType::Sqlite => sqlite_get_count(conn, query)?,
+        };
+
+        let ranges = match count {
+            0 => vec![(0, 0); num],
+            _ => partition_ranges(0, count - 1, num)?,
+        };
+        ranges
+            .into_iter()
+            .map(|(lower, upper)| {
+                let (offset, limit) = (lower as usize, (upper - lower) as usize);
+                match *self {
+                    SourceType::Postgres => {
+                        offset_partition_query(query, offset, limit, &PostgreSqlDialect {})
+                    }
+                    SourceType::Sqlite => {
+                        offset_partition_query(query, offset, limit, &SQLiteDialect {})
+                    }
+                }
+            })
+            .collect::<Result<Vec<_>>>()?
+    }
+
     pub fn get_part_query(&self, query: &str, col: &str, lower: i64, upper: i64) -> Result<String> {
         match *self {
             SourceType::Postgres => {
@@ -58,6 +221,100 @@ impl SourceType {
             }
         }
     }
+
+    /// Build the partition query of an incremental read of `query` on the watermark column `col`.
+    /// Returns the query selecting the rows after `last` together with the new watermark to resume
+    /// from, or `None` if there are no such rows. Only the watermark is queried here, the rows are
+    /// read by running the returned query through a source like any other partition query, as
+    /// `SourceConn::read_arrow_incremental` does.
+    /// The new watermark bounds the query, so that rows arriving in between are left for the next run.
+    #[throws(ConnectorAgentError)]
+    pub fn get_incremental_part_query(
+        &self,
+        conn: &str,
+        query: &str,
+        col: &str,
+        last: Option<&str>,
+    ) -> Option<(String, String)> {
+        let watermark = match *self {
+            SourceType::Postgres => pg_get_watermark(conn, query, col, last)?,
+            SourceType::Sqlite => sqlite_get_watermark(conn, query, col, last)?,
+        };
+
+        match watermark {
+            Some(watermark) => {
+                let part_query = match *self {
+                    SourceType::Postgres => {
+                        watermark_query(query, col, last, Some(&watermark), &PostgreSqlDialect {})?
+                    }
+                    SourceType::Sqlite => {
+                        watermark_query(query, col, last, Some(&watermark), &SQLiteDialect {})?
+                    }
+                };
+                Some((part_query, watermark))
+            }
+            None => None,
+        }
+    }
+}
+
+#[throws(ConnectorAgentError)]
+fn pg_get_count(conn: &str, query: &str) -> i64 {
+    let mut client = Client::connect(conn, NoTls)?;
+    let count_query = count_query(query, &PostgreSqlDialect {})?;
+    let row = client.query_one(count_query.as_str(), &[])?;
+    row.try_get(0)?
+}
+
+#[throws(ConnectorAgentError)]
+fn sqlite_get_count(conn: &str, query: &str) -> i64 {
+    let conn = Connection::open(&conn[9..])?;
+    let count_query = count_query(query, &SQLiteDialect {})?;
+    conn.query_row(count_query.as_str(), [], |row| row.get(0))?
+}
+
+#[throws(ConnectorAgentError)]
+fn pg_get_watermark(conn: &str, query: &str, col: &str, last: Option<&str>) -> Option<String> {
+    let mut client = Client::connect(conn, NoTls)?;
+    let max_query = watermark_max_query(query, col, last, &PostgreSqlDialect {})?;
+    let row = client.query_one(max_query.as_str(), &[])?;
+    row.try_get(0)?
+}
+
+#[throws(ConnectorAgentError)]
+fn sqlite_get_watermark(conn: &str, query: &str, col: &str, last: Option<&str>) -> Option<String> {
+    let conn = Connection::open(&conn[9..])?;
+    let max_query = watermark_max_query(query, col, last, &SQLiteDialect {})?;
+    conn.query_row(max_query.as_str(), [], |row| row.get(0))?
+}
+
+/// Split the inclusive range `[min, max]` into `num` half-open ranges of the same size,
+/// the last one also taking the remainder. The bounds are computed in `i128`, so a range spanning
+/// all of `i64` does not overflow, but `max` must be below `i64::MAX` to have an exclusive upper bound.
+#[throws(ConnectorAgentError)]
+pub fn partition_ranges(min: i64, max: i64, num: usize) -> Vec<(i64, i64)> {
+    if num == 0 {
+        throw!(anyhow!("Number of partitions should be positive"));
+    }
+    if min > max {
+        throw!(anyhow!("Invalid partition range [{}, {}]", min, max));
+    }
+
+    let (min, max, num) = (min as i128, max as i128, num as i128);
+    let partition_size = (max - min + 1) / num;
+    let bound = |b: i128| -> Result<i64> {
+        i64::try_from(b).map_err(|_| anyhow!("Partition bound {} does not fit in an i64", b).into())
+    };
+    (0..num)
+        .map(|i| {
+            let lower = min + i * partition_size;
+            let upper = match i == num - 1 {
+                true => max + 1,
+                false => min + (i + 1) * partition_size,
+            };
+            Ok((bound(lower)?, bound(upper)?))
+        })
+        .collect::<Result<Vec<_>>>()?
 }
 
 #[throws(ConnectorAgentError)]
@@ -66,30 +323,27 @@ fn pg_get_partition_range(conn: &str, query: &str, col: &str) -> (i64, i64) {
     let range_query = get_partition_range_query(query.clone(), col.clone(), &PostgreSqlDialect {})?;
     let row = client.query_one(range_query.as_str(), &[])?;
 
-    let col_type = PostgresTypeSystem::from(row.columns()[0].type_());
-    let (min_v, max_v) = match col_type {
-        PostgresTypeSystem::Int4(_) => {
-            let min_v: i32 = row.get(0);
-            let max_v: i32 = row.get(1);
-            (min_v as i64, max_v as i64)
-        }
-        PostgresTypeSystem::Int8(_) => {
-            let min_v: i64 = row.get(0);
-            let max_v: i64 = row.get(1);
-            (min_v, max_v)
+    // min and max are NULL if the query yields no rows, which gives the range (0, 0)
+    let col_type = row.columns()[0].type_();
+    let (min_v, max_v) = match PostgresTypeSystem::from_type(col_type) {
+        Some(PostgresTypeSystem::Int2(_)) => {
+            let min_v: Option<i16> = row.get(0);
+            let max_v: Option<i16> = row.get(1);
+            (min_v.unwrap_or(0) as i64, max_v.unwrap_or(0) as i64)
         }
-        PostgresTypeSystem::Float4(_) => {
-            let min_v: f32 = row.get(0);
-            let max_v: f32 = row.get(1);
-            (min_v as i64, max_v as i64)
+        Some(PostgresTypeSystem::Int4(_)) => {
+            let min_v: Option<i32> = row.get(0);
+            let max_v: Option<i32> = row.get(1);
+            (min_v.unwrap_or(0) as i64, max_v.unwrap_or(0) as i64)
         }
-        PostgresTypeSystem::Float8(_) => {
-            let min_v: f64 = row.get(0);
-            let max_v: f64 = row.get(1);
-            (min_v as i64, max_v as i64)
+        Some(PostgresTypeSystem::Int8(_)) => {
+            let min_v: Option<i64> = row.get(0);
+            let max_v: Option<i64> = row.get(1);
+            (min_v.unwrap_or(0), max_v.unwrap_or(0))
         }
         _ => throw!(anyhow!(
-            "Partition can only be done on int or float columns"
+            "Partition can only be done on integer columns, not {}",
+            col_type.name()
         )),
     };
 
@@ -106,9 +360,11 @@ fn sqlite_get_partition_range(conn: &str, query: &str, col: &str) -> (i64, i64)
     let mut error = None;
     let min_v = conn.query_row(min_query.as_str(), [], |row| {
         // declare type for count query will be None, only need to check the returned value type
+        // the value is NULL if the query yields no rows, which gives the range (0, 0)
         let col_type = row.get_ref(0)?.data_type();
         match col_type {
             Type::Integer => row.get(0),
+            Type::Null => Ok(0),
             _ => {
                 error = Some(anyhow!("Partition can only be done on integer columns"));
                 Ok(0)
@@ -123,6 +379,7 @@ fn sqlite_get_partition_range(conn: &str, query: &str, col: &str) -> (i64, i64)
         let col_type = row.get_ref(0)?.data_type();
         match col_type {
             Type::Integer => row.get(0),
+            Type::Null => Ok(0),
             _ => {
                 error = Some(anyhow!("Partition can only be done on integer columns"));
                 Ok(0)
@@ -0,0 +1,150 @@
+// the open connections are counted under the mutex of a condvar, which an atomic cannot replace
+#![allow(clippy::mutex_atomic)]
+
+use lazy_static::lazy_static;
+use r2d2::ManageConnection;
+use std::error::Error;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+// 0 stands for no limit
+static MAX_CONNECTIONS: AtomicUsize = AtomicUsize::new(0);
+
+lazy_static! {
+    // the number of open connections, notified whenever one is closed
+    static ref OPEN_CONNECTIONS: (Mutex<usize>, Condvar) = (Mutex::new(0), Condvar::new());
+}
+
+/// Cap the number of database connections open at once across all the source pools of the
+/// process, `None` (the default) for no cap. Opening a connection waits while the cap is reached,
+/// until another connection is closed or `PoolOptions::connection_timeout` expires.
+/// The cap applies to the pools of a `PostgresSource` created after it is set. These open their
+/// connections lazily and close them when returned instead of keeping them idle, so that the
+/// connections open on the server are the ones in use.
+/// A read holds one connection per partition, so the cap should be at least the largest number
+/// of partitions read at once, or reads sharing it may time out waiting on each other.
+pub fn set_max_connections(max: Option<usize>) {
+    MAX_CONNECTIONS.store(max.unwrap_or(0), Ordering::SeqCst);
+    // a raised cap may let the waiting connections open
+    OPEN_CONNECTIONS.1.notify_all();
+}
+
+pub fn max_connections() -> Option<usize> {
+    match MAX_CONNECTIONS.load(Ordering::SeqCst) {
+        0 => None,
+        max => Some(max),
+    }
+}
+
+/// The number of connections currently open by the source pools of the process.
+pub fn open_connections() -> usize {
+    *OPEN_CONNECTIONS.0.lock().unwrap()
+}
+
+/// A slot counted in `open_connections` until dropped.
+struct Permit;
+
+impl Permit {
+    /// Wait for a slot below `set_max_connections` for at most `timeout`.
+    fn acquire(timeout: Duration) -> Option<Permit> {
+        let (lock, closed) = &*OPEN_CONNECTIONS;
+        let deadline = Instant::now() + timeout;
+        let mut open = lock.lock().unwrap();
+        loop {
+            match max_connections() {
+                Some(max) if *open >= max => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return None;
+                    }
+                    open = closed.wait_timeout(open, deadline - now).unwrap().0;
+                }
+                _ => {
+                    *open += 1;
+                    return Some(Permit);
+                }
+            }
+        }
+    }
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        let (lock, closed) = &*OPEN_CONNECTIONS;
+        *lock.lock().unwrap() -= 1;
+        closed.notify_one();
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum LimitedError<E: Error + 'static> {
+    #[error("The limit of {0} open connections is reached.")]
+    LimitReached(usize),
+
+    #[error(transparent)]
+    Connection(E),
+}
+
+/// A connection of a `LimitedManager`, counted in `open_connections` until dropped.
+pub struct LimitedConnection<C> {
+    conn: C,
+    _permit: Permit,
+}
+
+impl<C> Deref for LimitedConnection<C> {
+    type Target = C;
+
+    fn deref(&self) -> &C {
+        &self.conn
+    }
+}
+
+impl<C> DerefMut for LimitedConnection<C> {
+    fn deref_mut(&mut self) -> &mut C {
+        &mut self.conn
+    }
+}
+
+/// Wraps the connection manager of a pool to respect `set_max_connections`, waiting at most
+/// `timeout` for a slot when opening a connection.
+#[derive(Debug)]
+pub struct LimitedManager<M> {
+    manager: M,
+    timeout: Duration,
+}
+
+impl<M> LimitedManager<M> {
+    pub fn new(manager: M, timeout: Duration) -> Self {
+        LimitedManager { manager, timeout }
+    }
+}
+
+impl<M: ManageConnection> ManageConnection for LimitedManager<M> {
+    type Connection = LimitedConnection<M::Connection>;
+    type Error = LimitedError<M::Error>;
+
+    fn connect(&self) -> Result<Self::Connection, Self::Error> {
+        let permit = Permit::acquire(self.timeout)
+            .ok_or_else(|| LimitedError::LimitReached(max_connections().unwrap_or(0)))?;
+        let conn = self.manager.connect().map_err(LimitedError::Connection)?;
+        Ok(LimitedConnection {
+            conn,
+            _permit: permit,
+        })
+    }
+
+    fn is_valid(&self, conn: &mut Self::Connection) -> Result<(), Self::Error> {
+        self.manager
+            .is_valid(&mut conn.conn)
+            .map_err(LimitedError::Connection)
+    }
+
+    /// Under a cap, the pool closes the connections returned to it, as an idle connection would
+    /// hold a slot that the pools of other reads wait for.
+    fn has_broken(&self, conn: &mut Self::Connection) -> bool {
+        max_connections().is_some() || self.manager.has_broken(&mut conn.conn)
+    }
+}
@@ -255,6 +255,10 @@ impl<'a> CSVSourcePartitionParser<'a> {
 
 impl<'a> PartitionParser<'a> for CSVSourcePartitionParser<'a> {
     type TypeSystem = DummyTypeSystem;
+
+    fn is_finished(&mut self) -> Result<bool> {
+        Ok(*self.counter >= self.records.len() * self.ncols)
+    }
 }
 
 impl<'r, 'a> Produce<'r, i64> for CSVSourcePartitionParser<'a> {
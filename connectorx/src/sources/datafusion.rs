@@ -0,0 +1,324 @@
+use super::{PartitionParser, Produce, Source, SourcePartition};
+use crate::data_order::DataOrder;
+use crate::dummy_typesystem::DummyTypeSystem;
+use crate::errors::{ConnectorAgentError, Result};
+use anyhow::anyhow;
+use arrow::array::{Array, ArrayRef, BooleanArray, Float64Array, Int64Array, StringArray};
+use arrow::compute::cast;
+use arrow::datatypes::{DataType as ArrowDataType, Field, Schema, SchemaRef};
+use arrow::record_batch::RecordBatch;
+use datafusion::execution::context::ExecutionContext;
+use datafusion::physical_plan::{ExecutionPlan, SendableRecordBatchStream};
+use fehler::{throw, throws};
+use futures::StreamExt;
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+
+/// A source reading the results of SQL queries executed through a DataFusion `ExecutionContext`.
+/// The tables need to be registered in the context before reading.
+pub struct DataFusionSource {
+    ctx: ExecutionContext,
+    queries: Vec<String>,
+    names: Vec<String>,
+    schema: Vec<DummyTypeSystem>,
+    cast_types: Vec<ArrowDataType>,
+}
+
+impl DataFusionSource {
+    pub fn new(ctx: ExecutionContext) -> Self {
+        DataFusionSource {
+            ctx,
+            queries: vec![],
+            names: vec![],
+            schema: vec![],
+            cast_types: vec![],
+        }
+    }
+}
+
+#[throws(ConnectorAgentError)]
+fn create_plan(ctx: &mut ExecutionContext, query: &str) -> Arc<dyn ExecutionPlan> {
+    let plan = ctx.create_logical_plan(query)?;
+    let plan = ctx.optimize(&plan)?;
+    ctx.create_physical_plan(&plan)?
+}
+
+/// The `DummyTypeSystem` of a DataFusion column, along with the arrow type the column is casted
+/// into so that the parser only deals with one array type per variant.
+#[throws(ConnectorAgentError)]
+fn arrow_to_dummy(dt: &ArrowDataType, nullable: bool) -> (DummyTypeSystem, ArrowDataType) {
+    use ArrowDataType::*;
+    match dt {
+        Int8 | Int16 | Int32 | Int64 | UInt8 | UInt16 | UInt32 | UInt64 => {
+            (DummyTypeSystem::I64(nullable), Int64)
+        }
+        Float32 | Float64 => (DummyTypeSystem::F64(nullable), Float64),
+        Boolean => (DummyTypeSystem::Bool(nullable), Boolean),
+        Utf8 | LargeUtf8 => (DummyTypeSystem::String(nullable), Utf8),
+        _ => throw!(anyhow!("DataFusion type {:?} is not supported", dt)),
+    }
+}
+
+impl Source for DataFusionSource {
+    const DATA_ORDERS: &'static [DataOrder] = &[DataOrder::RowMajor];
+    type Partition = DataFusionSourcePartition;
+    type TypeSystem = DummyTypeSystem;
+
+    #[throws(ConnectorAgentError)]
+    fn set_data_order(&mut self, data_order: DataOrder) {
+        if !matches!(data_order, DataOrder::RowMajor) {
+            throw!(ConnectorAgentError::UnsupportedDataOrder(data_order))
+        }
+    }
+
+    fn set_queries<Q: AsRef<str>>(&mut self, queries: &[Q]) {
+        self.queries = queries.iter().map(|q| q.as_ref().to_string()).collect();
+    }
+
+    fn fetch_metadata(&mut self) -> Result<()> {
+        assert!(!self.queries.is_empty());
+
+        // planning does not execute the query, the schema is known from the plan
+        let schema: SchemaRef = create_plan(&mut self.ctx, &self.queries[0])?.schema();
+        self.names = schema.fields().iter().map(|f| f.name().clone()).collect();
+        let (dummy_types, cast_types) = schema
+            .fields()
+            .iter()
+            .map(|f| arrow_to_dummy(f.data_type(), f.is_nullable()))
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .unzip();
+        self.schema = dummy_types;
+        self.cast_types = cast_types;
+        Ok(())
+    }
+
+    fn names(&self) -> Vec<String> {
+        self.names.clone()
+    }
+
+    fn schema(&self) -> Vec<Self::TypeSystem> {
+        self.schema.clone()
+    }
+
+    /// One partition per output partition of the plan of each query, all executed on the same
+    /// tokio runtime.
+    fn partition(mut self) -> Result<Vec<Self::Partition>> {
+        let rt = Arc::new(Runtime::new()?);
+        let mut ret = vec![];
+        for query in &self.queries {
+            let plan = create_plan(&mut self.ctx, query)?;
+            let fields = plan
+                .schema()
+                .fields()
+                .iter()
+                .zip(&self.cast_types)
+                .map(|(f, dt)| Field::new(f.name(), dt.clone(), f.is_nullable()))
+                .collect();
+            let arrow_schema = Arc::new(Schema::new(fields));
+            for partition in 0..plan.output_partitioning().partition_count() {
+                ret.push(DataFusionSourcePartition::new(
+                    plan.clone(),
+                    partition,
+                    rt.clone(),
+                    &self.schema,
+                    arrow_schema.clone(),
+                ));
+            }
+        }
+        Ok(ret)
+    }
+}
+
+pub struct DataFusionSourcePartition {
+    plan: Arc<dyn ExecutionPlan>,
+    partition: usize,
+    rt: Arc<Runtime>,
+    stream: Option<SendableRecordBatchStream>,
+    schema: Vec<DummyTypeSystem>,
+    arrow_schema: SchemaRef,
+    ncols: usize,
+}
+
+impl DataFusionSourcePartition {
+    pub fn new(
+        plan: Arc<dyn ExecutionPlan>,
+        partition: usize,
+        rt: Arc<Runtime>,
+        schema: &[DummyTypeSystem],
+        arrow_schema: SchemaRef,
+    ) -> Self {
+        Self {
+            plan,
+            partition,
+            rt,
+            stream: None,
+            schema: schema.to_vec(),
+            arrow_schema,
+            ncols: schema.len(),
+        }
+    }
+}
+
+impl SourcePartition for DataFusionSourcePartition {
+    type TypeSystem = DummyTypeSystem;
+    type Parser<'a> = DataFusionSourcePartitionParser<'a>;
+
+    /// Start executing the partition of the plan, its batches are pulled by the parser.
+    fn prepare(&mut self) -> Result<()> {
+        let stream = self.rt.block_on(self.plan.execute(self.partition))?;
+        self.stream = Some(stream);
+        Ok(())
+    }
+
+    fn parser(&mut self) -> Result<Self::Parser<'_>> {
+        let stream = self
+            .stream
+            .as_mut()
+            .ok_or_else(|| anyhow!("DataFusion partition is not prepared"))?;
+        Ok(DataFusionSourcePartitionParser::new(
+            stream,
+            &self.rt,
+            &self.schema,
+            self.arrow_schema.clone(),
+        ))
+    }
+
+    /// The number of rows is only known once the stream is exhausted.
+    fn nrows(&self) -> usize {
+        0
+    }
+
+    fn ncols(&self) -> usize {
+        self.ncols
+    }
+
+    fn nrows_exact(&self) -> bool {
+        false
+    }
+}
+
+pub struct DataFusionSourcePartitionParser<'a> {
+    stream: &'a mut SendableRecordBatchStream,
+    rt: &'a Runtime,
+    schema: &'a [DummyTypeSystem],
+    arrow_schema: SchemaRef,
+    batch: Option<RecordBatch>,
+    finished: bool,
+    current_row: usize,
+    current_col: usize,
+}
+
+impl<'a> DataFusionSourcePartitionParser<'a> {
+    fn new(
+        stream: &'a mut SendableRecordBatchStream,
+        rt: &'a Runtime,
+        schema: &'a [DummyTypeSystem],
+        arrow_schema: SchemaRef,
+    ) -> Self {
+        Self {
+            stream,
+            rt,
+            schema,
+            arrow_schema,
+            batch: None,
+            finished: false,
+            current_row: 0,
+            current_col: 0,
+        }
+    }
+
+    /// Pull the next batch out of the stream, with its columns casted into the partition schema.
+    fn next_batch(&mut self) -> Result<Option<RecordBatch>> {
+        let batch = match self.rt.block_on(self.stream.next()) {
+            Some(batch) => batch?,
+            None => return Ok(None),
+        };
+        let columns = batch
+            .columns()
+            .iter()
+            .zip(self.arrow_schema.fields())
+            .map(|(col, f)| Ok(cast(col, f.data_type())?))
+            .collect::<Result<Vec<ArrayRef>>>()?;
+        Ok(Some(RecordBatch::try_new(
+            self.arrow_schema.clone(),
+            columns,
+        )?))
+    }
+
+    /// Move past the batches whose rows are all read, including empty ones.
+    /// Returns false once the stream is exhausted.
+    fn skip_read_batches(&mut self) -> Result<bool> {
+        while !self.finished && self.current_row >= self.batch.as_ref().map_or(0, |b| b.num_rows())
+        {
+            self.batch = self.next_batch()?;
+            self.finished = self.batch.is_none();
+            self.current_row = 0;
+        }
+        Ok(!self.finished)
+    }
+
+    /// Returns the column array and the row index of the next value.
+    fn next_loc(&mut self) -> Result<(&ArrayRef, usize)> {
+        if !self.skip_read_batches()? {
+            throw!(anyhow!("DataFusion parser is already finished"));
+        }
+
+        let ncols = self.schema.len();
+        let (row, col) = (self.current_row, self.current_col);
+        self.current_row += (self.current_col + 1) / ncols;
+        self.current_col = (self.current_col + 1) % ncols;
+        let batch = self.batch.as_ref().unwrap();
+        Ok((batch.column(col), row))
+    }
+}
+
+impl<'a> PartitionParser<'a> for DataFusionSourcePartitionParser<'a> {
+    type TypeSystem = DummyTypeSystem;
+
+    fn is_finished(&mut self) -> Result<bool> {
+        Ok(!self.skip_read_batches()?)
+    }
+}
+
+fn downcast<T: 'static>(array: &ArrayRef) -> Result<&T> {
+    array
+        .as_any()
+        .downcast_ref::<T>()
+        .ok_or_else(|| anyhow!("cannot cast arrow array for produce").into())
+}
+
+macro_rules! impl_produce {
+    ($($t: ty => $A: ty,)+) => {
+        $(
+            impl<'r, 'a> Produce<'r, $t> for DataFusionSourcePartitionParser<'a> {
+                fn produce(&'r mut self) -> Result<$t> {
+                    let (array, ridx) = self.next_loc()?;
+                    let array = downcast::<$A>(array)?;
+                    if array.is_null(ridx) {
+                        throw!(ConnectorAgentError::cannot_produce::<$t>(Some("null".into())));
+                    }
+                    Ok(array.value(ridx).into())
+                }
+            }
+
+            impl<'r, 'a> Produce<'r, Option<$t>> for DataFusionSourcePartitionParser<'a> {
+                fn produce(&'r mut self) -> Result<Option<$t>> {
+                    let (array, ridx) = self.next_loc()?;
+                    let array = downcast::<$A>(array)?;
+                    match array.is_null(ridx) {
+                        true => Ok(None),
+                        false => Ok(Some(array.value(ridx).into())),
+                    }
+                }
+            }
+        )+
+    };
+}
+
+impl_produce!(
+    i64 => Int64Array,
+    f64 => Float64Array,
+    bool => BooleanArray,
+    String => StringArray,
+);
@@ -0,0 +1,68 @@
+use crate::errors::ConnectorAgentError;
+use arrow::array::{ArrayRef, StringArray};
+use arrow::datatypes::{DataType as ArrowDataType, Field, Schema};
+use arrow::record_batch::RecordBatch;
+use fehler::{throw, throws};
+use std::sync::{Arc, Mutex};
+
+/// Collects the rows a source could not read, together with the reason, instead of failing
+/// the whole read. It is shared by all the partitions of a source, and the read still fails
+/// once more than `max_rows` rows are diverted.
+#[derive(Clone)]
+pub struct DeadLetters {
+    max_rows: usize,
+    rows: Arc<Mutex<Vec<(String, String)>>>,
+}
+
+impl DeadLetters {
+    pub fn new(max_rows: usize) -> Self {
+        DeadLetters {
+            max_rows,
+            rows: Arc::new(Mutex::new(vec![])),
+        }
+    }
+
+    #[throws(ConnectorAgentError)]
+    pub(crate) fn push(&self, row: String, error: String) {
+        let mut rows = self.rows.lock().unwrap();
+        if rows.len() >= self.max_rows {
+            throw!(ConnectorAgentError::TooManyDeadLetters(
+                self.max_rows,
+                error
+            ));
+        }
+        rows.push((row, error));
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The diverted rows as `(raw row, error)` pairs.
+    pub fn rows(&self) -> Vec<(String, String)> {
+        self.rows.lock().unwrap().clone()
+    }
+
+    /// The diverted rows as a batch with the Utf8 columns `row` and `error`.
+    #[throws(ConnectorAgentError)]
+    pub fn batch(&self) -> RecordBatch {
+        let rows = self.rows();
+        let schema = Schema::new(vec![
+            Field::new("row", ArrowDataType::Utf8, false),
+            Field::new("error", ArrowDataType::Utf8, false),
+        ]);
+        let raw: Vec<&str> = rows.iter().map(|(r, _)| r.as_str()).collect();
+        let errors: Vec<&str> = rows.iter().map(|(_, e)| e.as_str()).collect();
+        RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(StringArray::from(raw)) as ArrayRef,
+                Arc::new(StringArray::from(errors)) as ArrayRef,
+            ],
+        )?
+    }
+}
@@ -112,7 +112,6 @@ impl SourcePartition for DummySourcePartition {
 
 pub struct DummySourcePartitionParser<'a> {
     counter: &'a mut usize,
-    #[allow(unused)]
     nrows: usize,
     ncols: usize,
 }
@@ -135,6 +134,10 @@ impl<'a> DummySourcePartitionParser<'a> {
 
 impl<'a> PartitionParser<'a> for DummySourcePartitionParser<'a> {
     type TypeSystem = DummyTypeSystem;
+
+    fn is_finished(&mut self) -> Result<bool> {
+        Ok(*self.counter >= self.nrows * self.ncols)
+    }
 }
 
 macro_rules! numeric_impl {
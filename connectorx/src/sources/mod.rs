@@ -1,14 +1,31 @@
 // When implementing a data source, be make sure to implement Queryable and
 // Producer for all supported types in crate::types::DataType.
 
+pub mod connection_limit;
 pub mod csv;
+#[cfg(feature = "datafusion")]
+pub mod datafusion;
+pub mod dead_letter;
 pub mod dummy;
 pub mod postgres;
 pub mod sqlite;
 
 use crate::data_order::DataOrder;
-use crate::errors::Result;
+use crate::errors::{ConnectorAgentError, Result};
 use crate::typesystem::{TypeAssoc, TypeSystem};
+use std::convert::TryInto;
+use std::fmt::Display;
+
+/// Convert a row count returned by the database into `usize`. `COUNT(*)` results are 64-bit,
+/// which do not fit `usize` on 32-bit targets once the table is large enough.
+pub fn checked_count<C>(count: C) -> Result<usize>
+where
+    C: TryInto<usize> + Display + Copy,
+{
+    count
+        .try_into()
+        .map_err(|_| ConnectorAgentError::CountOverflow(count.to_string()))
+}
 
 pub trait Source {
     /// Supported data orders, ordering by preference.
@@ -28,6 +45,12 @@ pub trait Source {
 
     fn schema(&self) -> Vec<Self::TypeSystem>;
 
+    /// The exact number of rows the queries are expected to yield in total, available after
+    /// `fetch_metadata`. If set, the dispatcher fails when a different number of rows is read.
+    fn expected_nrows(&self) -> Option<usize> {
+        None
+    }
+
     fn partition(self) -> Result<Vec<Self::Partition>>;
 }
 
@@ -47,6 +70,12 @@ pub trait SourcePartition: Sized {
 
     /// Number of cols this `DataSource` got.
     fn ncols(&self) -> usize;
+
+    /// Whether `nrows` is the exact number of rows. Otherwise it is only a size hint, and the
+    /// rows are read until the parser is finished, which needs a destination that can grow.
+    fn nrows_exact(&self) -> bool {
+        true
+    }
 }
 
 pub trait PartitionParser<'a> {
@@ -60,6 +89,11 @@ pub trait PartitionParser<'a> {
     {
         self.produce()
     }
+
+    /// Whether all the rows of the partition have been read. Streaming parsers may need to fetch
+    /// the next batch from the source to find out, hence `&mut self`. Reading past the end is an
+    /// error, so callers that do not know the row count upfront should check this before each row.
+    fn is_finished(&mut self) -> Result<bool>;
 }
 
 /// A type implemented `Produce<T>` means that it can produce a value `T` by consuming part of it's raw data buffer.
@@ -1,14 +1,21 @@
+mod point;
+mod range;
 mod typesystem;
 
 use crate::data_order::DataOrder;
 use crate::errors::{ConnectorAgentError, Result};
-use crate::sources::{PartitionParser, Produce, Source, SourcePartition};
+use crate::point::Point;
+use crate::range::Range;
+use crate::sources::connection_limit::{max_connections, LimitedManager};
+use crate::sources::dead_letter::DeadLetters;
+use crate::sources::{checked_count, PartitionParser, Produce, Source, SourcePartition};
 use crate::sql::{count_query, get_limit, limit1_query};
 use anyhow::anyhow;
 use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
-use csv::{ReaderBuilder, StringRecord, StringRecordsIntoIter};
-use fehler::throw;
+use csv::{ReaderBuilder, StringRecord, StringRecordsIntoIter, Terminator, WriterBuilder};
+use fehler::{throw, throws};
 use hex::decode;
+use itertools::Itertools;
 use log::debug;
 use postgres::{
     binary_copy::{BinaryCopyOutIter, BinaryCopyOutRow},
@@ -22,35 +29,101 @@ use serde_json::{from_str, Value};
 use sqlparser::dialect::PostgreSqlDialect;
 use std::io::BufRead;
 use std::marker::PhantomData;
+use std::time::Duration;
 pub use typesystem::PostgresTypeSystem;
 use uuid::Uuid;
 
-type PgManager = PostgresConnectionManager<NoTls>;
+type PgManager = LimitedManager<PostgresConnectionManager<NoTls>>;
 type PgConn = PooledConnection<PgManager>;
 
 pub enum Binary {}
 pub enum CSV {}
 
+/// Options of the connection pool of `PostgresSource`.
+#[derive(Debug, Clone)]
+pub struct PoolOptions {
+    /// How long to wait for a connection before failing, defaults to 30 seconds.
+    pub connection_timeout: Duration,
+    /// The number of idle connections kept in the pool, defaults to all of them.
+    pub min_idle: Option<u32>,
+}
+
+impl Default for PoolOptions {
+    fn default() -> Self {
+        PoolOptions {
+            connection_timeout: Duration::from_secs(30),
+            min_idle: None,
+        }
+    }
+}
+
+/// Get a connection from `pool`, r2d2 only fails after timing out on it.
+fn get_conn(pool: &Pool<PgManager>) -> Result<PgConn> {
+    pool.get().map_err(|e| {
+        ConnectorAgentError::ConnectionTimeout(pool.connection_timeout(), e.to_string())
+    })
+}
+
 pub struct PostgresSource<P> {
     pool: Pool<PgManager>,
+    session: Option<PgConn>,
+    single_session: bool,
     queries: Vec<String>,
     names: Vec<String>,
     schema: Vec<PostgresTypeSystem>,
+    type_names: Vec<String>,
     buf_size: usize,
+    count_rows: bool,
+    text_passthrough: bool,
+    text_columns: Vec<usize>,
+    validate_query: Option<String>,
+    expected_nrows: Option<usize>,
+    dead_letters: Option<DeadLetters>,
     _protocol: PhantomData<P>,
 }
 
 impl<P> PostgresSource<P> {
     pub fn new(conn: &str, nconn: usize) -> Result<Self> {
-        let manager = PostgresConnectionManager::new(conn.parse()?, NoTls);
-        let pool = Pool::builder().max_size(nconn as u32).build(manager)?;
+        Self::with_pool_options(conn, nconn, PoolOptions::default())
+    }
+
+    /// Like `new`, with the timeout and idle connections of the pool configured through `options`.
+    /// The pool is filled on creation, which fails if the connections cannot be made in time.
+    /// Under `set_max_connections`, the pool keeps no idle connections whatever `min_idle` is.
+    pub fn with_pool_options(conn: &str, nconn: usize, options: PoolOptions) -> Result<Self> {
+        let manager = LimitedManager::new(
+            PostgresConnectionManager::new(conn.parse()?, NoTls),
+            options.connection_timeout,
+        );
+        // idle connections would hold the slots other pools wait for
+        let min_idle = match max_connections() {
+            Some(_) => Some(0),
+            None => options.min_idle,
+        };
+        let pool = Pool::builder()
+            .max_size(nconn as u32)
+            .min_idle(min_idle)
+            .connection_timeout(options.connection_timeout)
+            .build(manager)
+            .map_err(|e| {
+                ConnectorAgentError::ConnectionTimeout(options.connection_timeout, e.to_string())
+            })?;
 
         Ok(Self {
             pool,
+            session: None,
+            single_session: false,
             queries: vec![],
             names: vec![],
             schema: vec![],
+            type_names: vec![],
             buf_size: 32,
+            count_rows: true,
+            text_passthrough: false,
+            text_columns: vec![],
+            validate_query: None,
+            expected_nrows: None,
+            dead_letters: None,
             _protocol: PhantomData,
         })
     }
@@ -58,6 +131,68 @@ impl<P> PostgresSource<P> {
     pub fn buf_size(&mut self, buf_size: usize) {
         self.buf_size = buf_size;
     }
+
+    /// Whether to run a `COUNT(*)` of each partition query before reading it, on by default.
+    /// Without the count the rows are streamed until the query is exhausted, which saves a scan
+    /// on large tables or views, but requires a destination that grows (e.g. `ArrowDestination`).
+    pub fn count_rows(&mut self, count_rows: bool) {
+        self.count_rows = count_rows;
+    }
+
+    /// Read the columns of a type without a specific handler (e.g. `jsonpath`, `inet` or
+    /// `interval`) as text, by casting them to `text` in the queries. Otherwise they are rejected.
+    pub fn text_passthrough(&mut self, enable: bool) {
+        self.text_passthrough = enable;
+    }
+
+    /// Check that the partition queries read as many rows in total as `query`, the query they
+    /// are split from, counts with a `COUNT(*)` when fetching the metadata. A mismatch fails the
+    /// read, e.g. when the partition predicates leave gaps or the table is modified concurrently.
+    pub fn validate_count(&mut self, query: &str) {
+        self.validate_query = Some(query.to_string());
+    }
+
+    /// Read all the queries through one connection as a single partition.
+    /// Temporary tables are only visible to the session that created them, so queries reading
+    /// them must turn this on, either here or by creating the tables through `execute`.
+    /// It is not detected from the queries: on another pooled connection a temporary table is
+    /// missing, or its name resolves to a permanent table of the same name instead.
+    pub fn single_session(&mut self, single_session: bool) {
+        self.single_session = single_session;
+    }
+
+    /// Execute statements (e.g. `CREATE TEMP TABLE`) on the session used for reading.
+    /// This turns on `single_session`.
+    pub fn execute(&mut self, sql: &str) -> Result<()> {
+        self.single_session = true;
+        self.session()?.batch_execute(sql)?;
+        Ok(())
+    }
+
+    /// The database type name of each column, available after `fetch_metadata`.
+    pub fn type_names(&self) -> Vec<String> {
+        self.type_names.clone()
+    }
+
+    fn session(&mut self) -> Result<&mut PgConn> {
+        if self.session.is_none() {
+            self.session = Some(get_conn(&self.pool)?);
+        }
+        Ok(self.session.as_mut().unwrap())
+    }
+}
+
+impl PostgresSource<CSV> {
+    /// Divert the rows holding a value that cannot be parsed into its column type to the
+    /// returned `DeadLetters` instead of failing the read, which still fails after `max_rows`
+    /// such rows. The row count of the partitions is then no longer exact, so the diverted
+    /// rows require a destination that grows (e.g. `ArrowDestination`). Only the CSV protocol
+    /// parses the cells in connectorx, so dead letters are not available with `Binary`.
+    pub fn dead_letters(&mut self, max_rows: usize) -> DeadLetters {
+        let dead_letters = DeadLetters::new(max_rows);
+        self.dead_letters = Some(dead_letters.clone());
+        dead_letters
+    }
 }
 
 impl<P> Source for PostgresSource<P>
@@ -83,7 +218,17 @@ where
     fn fetch_metadata(&mut self) -> Result<()> {
         assert!(self.queries.len() != 0);
 
-        let mut conn = self.pool.get()?;
+        if self.single_session && self.session.is_none() {
+            self.session = Some(get_conn(&self.pool)?);
+        }
+        let mut pooled;
+        let conn = match &mut self.session {
+            Some(conn) => conn,
+            None => {
+                pooled = get_conn(&self.pool)?;
+                &mut pooled
+            }
+        };
         let mut success = false;
         let mut zero_tuple = true;
         let mut error = None;
@@ -91,19 +236,30 @@ where
             // assuming all the partition queries yield same schema
             match conn.query_opt(&limit1_query(query, &PostgreSqlDialect {})?[..], &[]) {
                 Ok(Some(row)) => {
-                    let (names, types) = row
+                    self.names = vec![];
+                    self.schema = vec![];
+                    self.text_columns = vec![];
+                    for (i, col) in row.columns().into_iter().enumerate() {
+                        let ty = match PostgresTypeSystem::from_type(col.type_()) {
+                            Some(ty) => ty,
+                            None if self.text_passthrough => {
+                                self.text_columns.push(i);
+                                PostgresTypeSystem::Text(true)
+                            }
+                            None => throw!(anyhow!(
+                                "column {} has the unsupported type {}, enable text_passthrough to read it as text",
+                                col.name(),
+                                col.type_().name()
+                            )),
+                        };
+                        self.names.push(col.name().to_string());
+                        self.schema.push(ty);
+                    }
+                    self.type_names = row
                         .columns()
                         .into_iter()
-                        .map(|col| {
-                            (
-                                col.name().to_string(),
-                                PostgresTypeSystem::from(col.type_()),
-                            )
-                        })
-                        .unzip();
-
-                    self.names = names;
-                    self.schema = types;
+                        .map(|col| col.type_().name().to_string())
+                        .collect();
 
                     success = true;
                     zero_tuple = false;
@@ -118,6 +274,11 @@ where
             }
         }
 
+        if let Some(query) = &self.validate_query {
+            let row = conn.query_one(&count_query(query, &PostgreSqlDialect {})?[..], &[])?;
+            self.expected_nrows = Some(checked_count(row.get::<_, i64>(0))?);
+        }
+
         if !success {
             if zero_tuple {
                 // try to use COPY command get the column headers
@@ -131,6 +292,7 @@ where
                     .collect();
                 // set all columns as string (align with pandas)
                 self.schema = vec![PostgresTypeSystem::Text(false); self.names.len()];
+                self.type_names = vec!["text".to_string(); self.names.len()];
             } else {
                 throw!(anyhow!(
                     "Cannot get metadata for the queries, last error: {:?}",
@@ -150,22 +312,72 @@ where
         self.schema.clone()
     }
 
-    fn partition(self) -> Result<Vec<Self::Partition>> {
+    fn expected_nrows(&self) -> Option<usize> {
+        self.expected_nrows
+    }
+
+    fn partition(mut self) -> Result<Vec<Self::Partition>> {
+        if !self.text_columns.is_empty() {
+            self.queries = self
+                .queries
+                .iter()
+                .map(|q| text_cast_query(q, &self.names, &self.text_columns))
+                .collect();
+        }
+
+        if self.single_session {
+            let query = match self.queries.len() {
+                1 => self.queries[0].clone(),
+                _ => format!(
+                    "SELECT * FROM ({}) AS CXTMPTAB_SESSION",
+                    self.queries
+                        .iter()
+                        .map(|q| format!("({})", q))
+                        .join(" UNION ALL ")
+                ),
+            };
+            let conn = match self.session.take() {
+                Some(conn) => conn,
+                None => get_conn(&self.pool)?,
+            };
+
+            let mut partition =
+                PostgresSourcePartition::<P>::new(conn, &query, &self.schema, self.buf_size);
+            partition.count_rows = self.count_rows;
+            partition.dead_letters = self.dead_letters;
+            return Ok(vec![partition]);
+        }
+
         let mut ret = vec![];
         for query in self.queries {
-            let conn = self.pool.get()?;
-
-            ret.push(PostgresSourcePartition::<P>::new(
-                conn,
-                &query,
-                &self.schema,
-                self.buf_size,
-            ));
+            let conn = get_conn(&self.pool)?;
+
+            let mut partition =
+                PostgresSourcePartition::<P>::new(conn, &query, &self.schema, self.buf_size);
+            partition.count_rows = self.count_rows;
+            partition.dead_letters = self.dead_letters.clone();
+            ret.push(partition);
         }
         Ok(ret)
     }
 }
 
+/// Select the columns of `query`, casting the ones at `text_columns` to text.
+fn text_cast_query(query: &str, names: &[String], text_columns: &[usize]) -> String {
+    let columns = names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let name = format!("\"{}\"", name.replace('"', "\"\""));
+            match text_columns.contains(&i) {
+                true => format!("{}::text AS {}", name, name),
+                false => name,
+            }
+        })
+        .join(", ");
+    format!("SELECT {} FROM ({}) AS CXTMPTAB_TEXT", columns, query)
+}
+
 pub struct PostgresSourcePartition<P> {
     conn: PgConn,
     query: String,
@@ -173,6 +385,8 @@ pub struct PostgresSourcePartition<P> {
     nrows: usize,
     ncols: usize,
     buf_size: usize,
+    count_rows: bool,
+    dead_letters: Option<DeadLetters>,
     _protocol: PhantomData<P>,
 }
 
@@ -185,6 +399,8 @@ impl<P> PostgresSourcePartition<P> {
             nrows: 0,
             ncols: schema.len(),
             buf_size,
+            count_rows: true,
+            dead_letters: None,
             _protocol: PhantomData,
         }
     }
@@ -195,13 +411,16 @@ impl SourcePartition for PostgresSourcePartition<Binary> {
     type Parser<'a> = PostgresBinarySourcePartitionParser<'a>;
 
     fn prepare(&mut self) -> Result<()> {
+        if !self.count_rows {
+            return Ok(());
+        }
         let dialect = PostgreSqlDialect {};
         self.nrows = match get_limit(&self.query, &dialect)? {
             None => {
                 let row = self
                     .conn
                     .query_one(&count_query(&self.query, &dialect)?[..], &[])?;
-                row.get::<_, i64>(0) as usize
+                checked_count(row.get::<_, i64>(0))?
             }
             Some(n) => n,
         };
@@ -228,6 +447,10 @@ impl SourcePartition for PostgresSourcePartition<Binary> {
     fn ncols(&self) -> usize {
         self.ncols
     }
+
+    fn nrows_exact(&self) -> bool {
+        self.count_rows
+    }
 }
 
 impl SourcePartition for PostgresSourcePartition<CSV> {
@@ -235,10 +458,13 @@ impl SourcePartition for PostgresSourcePartition<CSV> {
     type Parser<'a> = PostgresCSVSourceParser<'a>;
 
     fn prepare(&mut self) -> Result<()> {
+        if !self.count_rows {
+            return Ok(());
+        }
         let row = self
             .conn
             .query_one(&count_query(&self.query, &PostgreSqlDialect {})?[..], &[])?;
-        self.nrows = row.get::<_, i64>(0) as usize;
+        self.nrows = checked_count(row.get::<_, i64>(0))?;
         Ok(())
     }
 
@@ -250,11 +476,9 @@ impl SourcePartition for PostgresSourcePartition<CSV> {
             .from_reader(reader)
             .into_records();
 
-        Ok(PostgresCSVSourceParser::new(
-            iter,
-            &self.schema,
-            self.buf_size,
-        ))
+        let mut parser = PostgresCSVSourceParser::new(iter, &self.schema, self.buf_size);
+        parser.dead_letters = self.dead_letters.clone();
+        Ok(parser)
     }
 
     fn nrows(&self) -> usize {
@@ -264,12 +488,17 @@ impl SourcePartition for PostgresSourcePartition<CSV> {
     fn ncols(&self) -> usize {
         self.ncols
     }
+
+    fn nrows_exact(&self) -> bool {
+        self.count_rows && self.dead_letters.is_none()
+    }
 }
 
 pub struct PostgresBinarySourcePartitionParser<'a> {
     iter: BinaryCopyOutIter<'a>,
     buf_size: usize,
     rowbuf: Vec<BinaryCopyOutRow>,
+    exhausted: bool,
     ncols: usize,
     current_col: usize,
     current_row: usize,
@@ -285,33 +514,42 @@ impl<'a> PostgresBinarySourcePartitionParser<'a> {
             iter,
             buf_size,
             rowbuf: Vec::with_capacity(buf_size),
+            exhausted: false,
             ncols: schema.len(),
             current_row: 0,
             current_col: 0,
         }
     }
 
-    fn next_loc(&mut self) -> Result<(usize, usize)> {
+    /// Refill `rowbuf` with the next `buf_size` rows once all the buffered ones are consumed.
+    /// `rowbuf` stays empty when the source has no more rows, which are not polled for again once
+    /// the COPY is exhausted: its stream fails with `Closed` when polled past the end.
+    fn fill_rowbuf(&mut self) -> Result<()> {
         if self.current_row >= self.rowbuf.len() {
             if !self.rowbuf.is_empty() {
                 self.rowbuf.drain(..);
             }
 
-            for _ in 0..self.buf_size {
+            while !self.exhausted && self.rowbuf.len() < self.buf_size {
                 match self.iter.next()? {
                     Some(row) => {
                         self.rowbuf.push(row);
                     }
-                    None => break,
+                    None => self.exhausted = true,
                 }
             }
 
-            if self.rowbuf.is_empty() {
-                throw!(anyhow!("Postgres EOF"));
-            }
             self.current_row = 0;
             self.current_col = 0;
         }
+        Ok(())
+    }
+
+    fn next_loc(&mut self) -> Result<(usize, usize)> {
+        self.fill_rowbuf()?;
+        if self.rowbuf.is_empty() {
+            throw!(anyhow!("Postgres parser is already finished"));
+        }
 
         let ret = (self.current_row, self.current_col);
         self.current_row += (self.current_col + 1) / self.ncols;
@@ -322,6 +560,11 @@ impl<'a> PostgresBinarySourcePartitionParser<'a> {
 
 impl<'a> PartitionParser<'a> for PostgresBinarySourcePartitionParser<'a> {
     type TypeSystem = PostgresTypeSystem;
+
+    fn is_finished(&mut self) -> Result<bool> {
+        self.fill_rowbuf()?;
+        Ok(self.rowbuf.is_empty())
+    }
 }
 
 macro_rules! impl_produce {
@@ -365,12 +608,18 @@ impl_produce!(
     NaiveDate,
     Uuid,
     Value,
+    Range<i32>,
+    Range<NaiveDate>,
+    Point,
 );
 
 pub struct PostgresCSVSourceParser<'a> {
     iter: StringRecordsIntoIter<CopyOutReader<'a>>,
     buf_size: usize,
     rowbuf: Vec<StringRecord>,
+    exhausted: bool,
+    schema: Vec<PostgresTypeSystem>,
+    dead_letters: Option<DeadLetters>,
     ncols: usize,
     current_col: usize,
     current_row: usize,
@@ -386,32 +635,58 @@ impl<'a> PostgresCSVSourceParser<'a> {
             iter,
             buf_size,
             rowbuf: Vec::with_capacity(buf_size),
+            exhausted: false,
+            schema: schema.to_vec(),
+            dead_letters: None,
             ncols: schema.len(),
             current_row: 0,
             current_col: 0,
         }
     }
 
-    fn next_loc(&mut self) -> Result<(usize, usize)> {
+    /// Refill `rowbuf` with the next `buf_size` rows once all the buffered ones are consumed.
+    /// `rowbuf` stays empty when the source has no more rows. With dead letters, the rows that
+    /// cannot be parsed are diverted to them and not buffered.
+    fn fill_rowbuf(&mut self) -> Result<()> {
         if self.current_row >= self.rowbuf.len() {
             if !self.rowbuf.is_empty() {
                 self.rowbuf.drain(..);
             }
 
-            for _ in 0..self.buf_size {
-                if let Some(row) = self.iter.next() {
-                    self.rowbuf.push(row?);
-                } else {
-                    break;
+            while !self.exhausted && self.rowbuf.len() < self.buf_size {
+                let row = match self.iter.next() {
+                    Some(row) => row?,
+                    None => {
+                        self.exhausted = true;
+                        break;
+                    }
+                };
+                if row.len() != self.ncols {
+                    throw!(ConnectorAgentError::ColumnCountMismatch(
+                        self.ncols,
+                        row.len()
+                    ));
                 }
+                if let Some(dead_letters) = &self.dead_letters {
+                    if let Some(error) = check_record(&self.schema, &row) {
+                        dead_letters.push(raw_record(&row)?, error)?;
+                        continue;
+                    }
+                }
+                self.rowbuf.push(row);
             }
 
-            if self.rowbuf.is_empty() {
-                throw!(anyhow!("Postgres EOF"));
-            }
             self.current_row = 0;
             self.current_col = 0;
         }
+        Ok(())
+    }
+
+    fn next_loc(&mut self) -> Result<(usize, usize)> {
+        self.fill_rowbuf()?;
+        if self.rowbuf.is_empty() {
+            throw!(anyhow!("Postgres parser is already finished"));
+        }
 
         let ret = (self.current_row, self.current_col);
         self.current_row += (self.current_col + 1) / self.ncols;
@@ -422,155 +697,197 @@ impl<'a> PostgresCSVSourceParser<'a> {
 
 impl<'a> PartitionParser<'a> for PostgresCSVSourceParser<'a> {
     type TypeSystem = PostgresTypeSystem;
+
+    fn is_finished(&mut self) -> Result<bool> {
+        self.fill_rowbuf()?;
+        Ok(self.rowbuf.is_empty())
+    }
 }
 
-macro_rules! impl_csv_produce {
+/// The CSV line of `record` as postgres wrote it, quoting the cells that need to be quoted.
+#[throws(ConnectorAgentError)]
+fn raw_record(record: &StringRecord) -> String {
+    let mut writer = WriterBuilder::new()
+        .terminator(Terminator::Any(b'\n'))
+        .from_writer(vec![]);
+    writer.write_record(record)?;
+    let mut raw = writer
+        .into_inner()
+        .map_err(|e| anyhow!("cannot write dead letter: {}", e))?;
+    raw.pop(); // remove the terminator
+    String::from_utf8(raw).map_err(|e| anyhow!("cannot write dead letter: {}", e))?
+}
+
+/// Whether each cell of `record` parses into its column type with the same `ParseCSV` impls as
+/// the `Produce` impls below, returning the error of the first one that does not.
+fn check_record(schema: &[PostgresTypeSystem], record: &StringRecord) -> Option<String> {
+    schema
+        .iter()
+        .zip(record.iter())
+        .enumerate()
+        .find_map(|(i, (ty, cell))| {
+            check_cell(*ty, cell)
+                .err()
+                .map(|e| format!("column {}: {}", i, e))
+        })
+}
+
+fn check_cell(ty: PostgresTypeSystem, cell: &str) -> Result<()> {
+    use PostgresTypeSystem::*;
+    if cell.is_empty() {
+        return Ok(());
+    }
+    match ty {
+        Bool(_) => bool::parse_csv(cell).map(|_| ()),
+        Float4(_) => f32::parse_csv(cell).map(|_| ()),
+        Float8(_) => f64::parse_csv(cell).map(|_| ()),
+        Numeric(_) => Decimal::parse_csv(cell).map(|_| ()),
+        Int2(_) => i16::parse_csv(cell).map(|_| ()),
+        Int4(_) => i32::parse_csv(cell).map(|_| ()),
+        Int8(_) => i64::parse_csv(cell).map(|_| ()),
+        Char(_) => i8::parse_csv(cell).map(|_| ()),
+        Date(_) => NaiveDate::parse_csv(cell).map(|_| ()),
+        Time(_) => NaiveTime::parse_csv(cell).map(|_| ()),
+        Timestamp(_) => NaiveDateTime::parse_csv(cell).map(|_| ()),
+        TimestampTz(_) => DateTime::<Utc>::parse_csv(cell).map(|_| ()),
+        UUID(_) => Uuid::parse_csv(cell).map(|_| ()),
+        JSON(_) | JSONB(_) => Value::parse_csv(cell).map(|_| ()),
+        ByteA(_) => Vec::<u8>::parse_csv(cell).map(|_| ()),
+        Int4Range(_) => Range::<i32>::parse_csv(cell).map(|_| ()),
+        DateRange(_) => Range::<NaiveDate>::parse_csv(cell).map(|_| ()),
+        // the variant shadows the type here
+        Point(_) => crate::point::Point::parse_csv(cell).map(|_| ()),
+        BpChar(_) | VarChar(_) | Text(_) | Enum(_) => Ok(()),
+    }
+}
+
+/// Parse a non-null cell of the CSV postgres writes into a value of its column type.
+trait ParseCSV: Sized {
+    fn parse_csv(cell: &str) -> Result<Self>;
+}
+
+macro_rules! impl_parse_csv_from_str {
     ($($t: ty,)+) => {
         $(
-            impl<'r, 'a> Produce<'r, $t> for PostgresCSVSourceParser<'a> {
-                fn produce(&'r mut self) -> Result<$t> {
-                    let (ridx, cidx) = self.next_loc()?;
-                    self.rowbuf[ridx][cidx].parse().map_err(|_| {
-                        ConnectorAgentError::cannot_produce::<$t>(Some(self.rowbuf[ridx][cidx].into()))
-                    })
-                }
-            }
-
-            impl<'r, 'a> Produce<'r, Option<$t>> for PostgresCSVSourceParser<'a> {
-                fn produce(&'r mut self) -> Result<Option<$t>> {
-                    let (ridx, cidx) = self.next_loc()?;
-                    match &self.rowbuf[ridx][cidx][..] {
-                        "" => Ok(None),
-                        v => Ok(Some(v.parse().map_err(|_| {
-                            ConnectorAgentError::cannot_produce::<$t>(Some(self.rowbuf[ridx][cidx].into()))
-                        })?)),
-                    }
+            impl ParseCSV for $t {
+                fn parse_csv(cell: &str) -> Result<$t> {
+                    cell.parse()
+                        .map_err(|_| ConnectorAgentError::cannot_produce::<$t>(Some(cell.into())))
                 }
             }
         )+
     };
 }
 
-impl_csv_produce!(i8, i16, i32, i64, f32, f64, Decimal, Uuid,);
-
-impl<'r, 'a> Produce<'r, bool> for PostgresCSVSourceParser<'a> {
-    fn produce(&mut self) -> Result<bool> {
-        let (ridx, cidx) = self.next_loc()?;
-        let ret = match &self.rowbuf[ridx][cidx][..] {
-            "t" => true,
-            "f" => false,
-            _ => throw!(ConnectorAgentError::cannot_produce::<bool>(Some(
-                self.rowbuf[ridx][cidx].into()
-            ))),
-        };
-        Ok(ret)
-    }
-}
+impl_parse_csv_from_str!(
+    i8,
+    i16,
+    i32,
+    i64,
+    f32,
+    f64,
+    Decimal,
+    Uuid,
+    DateTime<Utc>,
+    Range<i32>,
+    Range<NaiveDate>,
+    Point,
+);
 
-impl<'r, 'a> Produce<'r, Option<bool>> for PostgresCSVSourceParser<'a> {
-    fn produce(&mut self) -> Result<Option<bool>> {
-        let (ridx, cidx) = self.next_loc()?;
-        let ret = match &self.rowbuf[ridx][cidx][..] {
-            "" => None,
-            "t" => Some(true),
-            "f" => Some(false),
+impl ParseCSV for bool {
+    fn parse_csv(cell: &str) -> Result<bool> {
+        match cell {
+            "t" => Ok(true),
+            "f" => Ok(false),
             _ => throw!(ConnectorAgentError::cannot_produce::<bool>(Some(
-                self.rowbuf[ridx][cidx].into()
+                cell.into()
             ))),
-        };
-        Ok(ret)
+        }
     }
 }
 
-impl<'r, 'a> Produce<'r, DateTime<Utc>> for PostgresCSVSourceParser<'a> {
-    fn produce(&mut self) -> Result<DateTime<Utc>> {
-        let (ridx, cidx) = self.next_loc()?;
-        self.rowbuf[ridx][cidx].parse().map_err(|_| {
-            ConnectorAgentError::cannot_produce::<DateTime<Utc>>(Some(
-                self.rowbuf[ridx][cidx].into(),
-            ))
-        })
+impl ParseCSV for NaiveDate {
+    fn parse_csv(cell: &str) -> Result<NaiveDate> {
+        NaiveDate::parse_from_str(cell, "%Y-%m-%d")
+            .map_err(|_| ConnectorAgentError::cannot_produce::<NaiveDate>(Some(cell.into())))
     }
 }
 
-impl<'r, 'a> Produce<'r, Option<DateTime<Utc>>> for PostgresCSVSourceParser<'a> {
-    fn produce(&mut self) -> Result<Option<DateTime<Utc>>> {
-        let (ridx, cidx) = self.next_loc()?;
-        match &self.rowbuf[ridx][cidx][..] {
-            "" => Ok(None),
-            v => Ok(Some(v.parse().map_err(|_| {
-                ConnectorAgentError::cannot_produce::<DateTime<Utc>>(Some(v.into()))
-            })?)),
-        }
+impl ParseCSV for NaiveDateTime {
+    fn parse_csv(cell: &str) -> Result<NaiveDateTime> {
+        NaiveDateTime::parse_from_str(cell, "%Y-%m-%d %H:%M:%S")
+            .map_err(|_| ConnectorAgentError::cannot_produce::<NaiveDateTime>(Some(cell.into())))
     }
 }
 
-impl<'r, 'a> Produce<'r, NaiveDate> for PostgresCSVSourceParser<'a> {
-    fn produce(&mut self) -> Result<NaiveDate> {
-        let (ridx, cidx) = self.next_loc()?;
-        NaiveDate::parse_from_str(&self.rowbuf[ridx][cidx], "%Y-%m-%d").map_err(|_| {
-            ConnectorAgentError::cannot_produce::<NaiveDate>(Some(self.rowbuf[ridx][cidx].into()))
-        })
+impl ParseCSV for NaiveTime {
+    fn parse_csv(cell: &str) -> Result<NaiveTime> {
+        NaiveTime::parse_from_str(cell, "%H:%M:%S")
+            .map_err(|_| ConnectorAgentError::cannot_produce::<NaiveTime>(Some(cell.into())))
     }
 }
 
-impl<'r, 'a> Produce<'r, Option<NaiveDate>> for PostgresCSVSourceParser<'a> {
-    fn produce(&mut self) -> Result<Option<NaiveDate>> {
-        let (ridx, cidx) = self.next_loc()?;
-        match &self.rowbuf[ridx][cidx][..] {
-            "" => Ok(None),
-            v => Ok(Some(NaiveDate::parse_from_str(v, "%Y-%m-%d").map_err(
-                |_| ConnectorAgentError::cannot_produce::<NaiveDate>(Some(v.into())),
-            )?)),
+impl ParseCSV for Vec<u8> {
+    fn parse_csv(cell: &str) -> Result<Vec<u8>> {
+        // postgres writes bytea in the hex format, prefixed with \x
+        match cell.strip_prefix("\\x") {
+            Some(hex) => Ok(decode(hex)?),
+            None => throw!(ConnectorAgentError::cannot_produce::<Vec<u8>>(Some(
+                cell.into()
+            ))),
         }
     }
 }
 
-impl<'r, 'a> Produce<'r, NaiveDateTime> for PostgresCSVSourceParser<'a> {
-    fn produce(&mut self) -> Result<NaiveDateTime> {
-        let (ridx, cidx) = self.next_loc()?;
-        NaiveDateTime::parse_from_str(&self.rowbuf[ridx][cidx], "%Y-%m-%d %H:%M:%S").map_err(|_| {
-            ConnectorAgentError::cannot_produce::<NaiveDateTime>(Some(
-                self.rowbuf[ridx][cidx].into(),
-            ))
-        })
+impl ParseCSV for Value {
+    fn parse_csv(cell: &str) -> Result<Value> {
+        from_str(cell).map_err(|_| ConnectorAgentError::cannot_produce::<Value>(Some(cell.into())))
     }
 }
 
-impl<'r, 'a> Produce<'r, Option<NaiveDateTime>> for PostgresCSVSourceParser<'a> {
-    fn produce(&mut self) -> Result<Option<NaiveDateTime>> {
-        let (ridx, cidx) = self.next_loc()?;
-        match &self.rowbuf[ridx][cidx][..] {
-            "" => Ok(None),
-            v => Ok(Some(
-                NaiveDateTime::parse_from_str(v, "%Y-%m-%d %H:%M:%S").map_err(|_| {
-                    ConnectorAgentError::cannot_produce::<NaiveDateTime>(Some(v.into()))
-                })?,
-            )),
-        }
-    }
-}
+macro_rules! impl_csv_produce {
+    ($($t: ty,)+) => {
+        $(
+            impl<'r, 'a> Produce<'r, $t> for PostgresCSVSourceParser<'a> {
+                fn produce(&'r mut self) -> Result<$t> {
+                    let (ridx, cidx) = self.next_loc()?;
+                    <$t>::parse_csv(&self.rowbuf[ridx][cidx])
+                }
+            }
 
-impl<'r, 'a> Produce<'r, NaiveTime> for PostgresCSVSourceParser<'a> {
-    fn produce(&mut self) -> Result<NaiveTime> {
-        let (ridx, cidx) = self.next_loc()?;
-        NaiveTime::parse_from_str(&self.rowbuf[ridx][cidx], "%H:%M:%S").map_err(|_| {
-            ConnectorAgentError::cannot_produce::<NaiveTime>(Some(self.rowbuf[ridx][cidx].into()))
-        })
-    }
+            impl<'r, 'a> Produce<'r, Option<$t>> for PostgresCSVSourceParser<'a> {
+                fn produce(&'r mut self) -> Result<Option<$t>> {
+                    let (ridx, cidx) = self.next_loc()?;
+                    match &self.rowbuf[ridx][cidx][..] {
+                        "" => Ok(None),
+                        v => Ok(Some(<$t>::parse_csv(v)?)),
+                    }
+                }
+            }
+        )+
+    };
 }
 
-impl<'r, 'a> Produce<'r, Option<NaiveTime>> for PostgresCSVSourceParser<'a> {
-    fn produce(&mut self) -> Result<Option<NaiveTime>> {
-        let (ridx, cidx) = self.next_loc()?;
-        match &self.rowbuf[ridx][cidx][..] {
-            "" => Ok(None),
-            v => Ok(Some(NaiveTime::parse_from_str(v, "%H:%M:%S").map_err(
-                |_| ConnectorAgentError::cannot_produce::<NaiveTime>(Some(v.into())),
-            )?)),
-        }
-    }
-}
+impl_csv_produce!(
+    i8,
+    i16,
+    i32,
+    i64,
+    f32,
+    f64,
+    Decimal,
+    bool,
+    Vec<u8>,
+    NaiveTime,
+    NaiveDateTime,
+    DateTime<Utc>,
+    NaiveDate,
+    Uuid,
+    Value,
+    Range<i32>,
+    Range<NaiveDate>,
+    Point,
+);
 
 impl<'r, 'a> Produce<'r, &'r str> for PostgresCSVSourceParser<'a> {
     fn produce(&'r mut self) -> Result<&'r str> {
@@ -588,41 +905,3 @@ impl<'r, 'a> Produce<'r, Option<&'r str>> for PostgresCSVSourceParser<'a> {
         }
     }
 }
-
-impl<'r, 'a> Produce<'r, Vec<u8>> for PostgresCSVSourceParser<'a> {
-    fn produce(&'r mut self) -> Result<Vec<u8>> {
-        let (ridx, cidx) = self.next_loc()?;
-        Ok(decode(&self.rowbuf[ridx][cidx][2..])?) // escape \x in the beginning
-    }
-}
-
-impl<'r, 'a> Produce<'r, Option<Vec<u8>>> for PostgresCSVSourceParser<'a> {
-    fn produce(&'r mut self) -> Result<Option<Vec<u8>>> {
-        let (ridx, cidx) = self.next_loc()?;
-        match &self.rowbuf[ridx][cidx][2..] {
-            // escape \x in the beginning
-            "" => Ok(None),
-            v => Ok(Some(decode(&v)?)),
-        }
-    }
-}
-
-impl<'r, 'a> Produce<'r, Value> for PostgresCSVSourceParser<'a> {
-    fn produce(&'r mut self) -> Result<Value> {
-        let (ridx, cidx) = self.next_loc()?;
-        let v = &self.rowbuf[ridx][cidx];
-        from_str(v).map_err(|_| ConnectorAgentError::cannot_produce::<Value>(Some(v.into())))
-    }
-}
-
-impl<'r, 'a> Produce<'r, Option<Value>> for PostgresCSVSourceParser<'a> {
-    fn produce(&'r mut self) -> Result<Option<Value>> {
-        let (ridx, cidx) = self.next_loc()?;
-
-        match &self.rowbuf[ridx][cidx][..] {
-            "" => Ok(None),
-            v => from_str(v)
-                .map_err(|_| ConnectorAgentError::cannot_produce::<Value>(Some(v.into()))),
-        }
-    }
-}
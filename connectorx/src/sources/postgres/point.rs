@@ -0,0 +1,22 @@
+use crate::point::Point;
+use postgres::types::{FromSql, Type};
+use std::convert::TryInto;
+use std::error::Error;
+
+/// Decode the binary format of points: the big endian x and y coordinates.
+impl<'a> FromSql<'a> for Point {
+    fn from_sql(_ty: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        if raw.len() != 16 {
+            return Err(format!("invalid point buffer of {} bytes", raw.len()).into());
+        }
+        let (x, y) = raw.split_at(8);
+        Ok(Point::new(
+            f64::from_be_bytes(x.try_into()?),
+            f64::from_be_bytes(y.try_into()?),
+        ))
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        *ty == Type::POINT
+    }
+}
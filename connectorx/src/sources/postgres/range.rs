@@ -0,0 +1,59 @@
+use crate::range::Range;
+use postgres::types::{FromSql, Kind, Type};
+use std::convert::TryInto;
+use std::error::Error;
+
+// flags of the binary range format, see src/include/utils/rangetypes.h in postgres
+const RANGE_EMPTY: u8 = 0x01;
+const RANGE_LB_INC: u8 = 0x02;
+const RANGE_UB_INC: u8 = 0x04;
+const RANGE_LB_INF: u8 = 0x08;
+const RANGE_UB_INF: u8 = 0x10;
+
+/// Decode the binary format of range types: a flag byte followed by the length prefixed
+/// lower and upper bounds, each present only if the range is bounded on that side.
+impl<'a, T: FromSql<'a>> FromSql<'a> for Range<T> {
+    fn from_sql(ty: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        let inner = match ty.kind() {
+            Kind::Range(inner) => inner,
+            _ => return Err(format!("{} is not a range type", ty).into()),
+        };
+        let (&flags, mut rest) = raw.split_first().ok_or("empty range buffer")?;
+        if flags & RANGE_EMPTY != 0 {
+            return Ok(Range::empty());
+        }
+
+        let mut bound = |bounded: bool| -> Result<Option<T>, Box<dyn Error + Sync + Send>> {
+            if !bounded {
+                return Ok(None);
+            }
+            if rest.len() < 4 {
+                return Err("truncated range bound".into());
+            }
+            let (len, tail) = rest.split_at(4);
+            let len = i32::from_be_bytes(len.try_into()?) as usize;
+            if tail.len() < len {
+                return Err("truncated range bound".into());
+            }
+            let (value, tail) = tail.split_at(len);
+            rest = tail;
+            Ok(Some(T::from_sql(inner, value)?))
+        };
+        let lower = bound(flags & RANGE_LB_INF == 0)?;
+        let upper = bound(flags & RANGE_UB_INF == 0)?;
+
+        Ok(Range::new(
+            lower,
+            upper,
+            flags & RANGE_LB_INC != 0,
+            flags & RANGE_UB_INC != 0,
+        ))
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        match ty.kind() {
+            Kind::Range(inner) => T::accepts(inner),
+            _ => false,
+        }
+    }
+}
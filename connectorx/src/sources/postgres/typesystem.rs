@@ -1,3 +1,5 @@
+use crate::point::Point;
+use crate::range::Range;
 use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use postgres::types::Type;
 use rust_decimal::Decimal;
@@ -26,6 +28,9 @@ pub enum PostgresTypeSystem {
     JSON(bool),
     JSONB(bool),
     Enum(bool),
+    Int4Range(bool),
+    DateRange(bool),
+    Point(bool),
 }
 
 impl_typesystem! {
@@ -47,13 +52,17 @@ impl_typesystem! {
         { Date => NaiveDate }
         { UUID => Uuid }
         { JSON | JSONB => Value }
+        { Int4Range => Range<i32> }
+        { DateRange => Range<NaiveDate> }
+        { Point => Point }
     }
 }
 
-impl<'a> From<&'a Type> for PostgresTypeSystem {
-    fn from(ty: &'a Type) -> PostgresTypeSystem {
+impl PostgresTypeSystem {
+    /// The type a Postgres column is read as, `None` if the column type is not supported.
+    pub fn from_type(ty: &Type) -> Option<PostgresTypeSystem> {
         use PostgresTypeSystem::*;
-        match ty.name() {
+        let ret = match ty.name() {
             "int2" => Int2(true),
             "int4" => Int4(true),
             "int8" => Int8(true),
@@ -73,11 +82,21 @@ impl<'a> From<&'a Type> for PostgresTypeSystem {
             "uuid" => UUID(true),
             "json" => JSON(true),
             "jsonb" => JSONB(true),
+            "int4range" => Int4Range(true),
+            "daterange" => DateRange(true),
+            "point" => Point(true),
             _ => match ty.kind() {
                 postgres::types::Kind::Enum(_) => Enum(true),
-                _ => unimplemented!("{}", ty.name()),
+                _ => return None,
             },
-        }
+        };
+        Some(ret)
+    }
+}
+
+impl<'a> From<&'a Type> for PostgresTypeSystem {
+    fn from(ty: &'a Type) -> PostgresTypeSystem {
+        PostgresTypeSystem::from_type(ty).unwrap_or_else(|| unimplemented!("{}", ty.name()))
     }
 }
 
@@ -106,6 +125,9 @@ impl<'a> From<PostgresTypeSystem> for Type {
             JSON(_) => Type::JSON,
             JSONB(_) => Type::JSONB,
             Enum(_) => Type::TEXT,
+            Int4Range(_) => Type::INT4_RANGE,
+            DateRange(_) => Type::DATE_RANGE,
+            Point(_) => Type::POINT,
         }
     }
 }
@@ -4,7 +4,7 @@ use fallible_streaming_iterator::FallibleStreamingIterator;
 
 use crate::data_order::DataOrder;
 use crate::errors::{ConnectorAgentError, Result};
-use crate::sources::{PartitionParser, Produce, Source, SourcePartition};
+use crate::sources::{checked_count, PartitionParser, Produce, Source, SourcePartition};
 use crate::sql::{count_query, get_limit, limit1_query};
 use anyhow::anyhow;
 use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
@@ -14,7 +14,11 @@ use log::debug;
 use owning_ref::OwningHandle;
 use r2d2::{Pool, PooledConnection};
 use r2d2_sqlite::SqliteConnectionManager;
-use rusqlite::{Row, Rows, Statement};
+use rusqlite::{
+    types::{FromSql, Type},
+    Row, Rows, Statement,
+};
+use serde_json::{from_str, Value};
 use sqlparser::dialect::SQLiteDialect;
 pub use typesystem::SqliteTypeSystem;
 
@@ -26,11 +30,16 @@ pub struct SqliteSource {
     queries: Vec<String>,
     names: Vec<String>,
     schema: Vec<SqliteTypeSystem>,
+    parse_json: bool,
 }
 
 impl SqliteSource {
+    /// With the `csvtab` feature, the csv virtual table module is loaded on every connection so
+    /// that tables created with `CREATE VIRTUAL TABLE ... USING csv(filename=...)` can be read.
     pub fn new(conn: &str, nconn: usize) -> Result<Self> {
         let manager = SqliteConnectionManager::file(conn);
+        #[cfg(feature = "csvtab")]
+        let manager = manager.with_init(|c| rusqlite::vtab::csvtab::load_module(c));
         let pool = r2d2::Pool::builder()
             .max_size(nconn as u32)
             .build(manager)?;
@@ -40,8 +49,15 @@ impl SqliteSource {
             queries: vec![],
             names: vec![],
             schema: vec![],
+            parse_json: true,
         })
     }
+
+    /// Whether columns declared as `JSON` are parsed into `serde_json::Value`, failing on invalid json.
+    /// Otherwise they are read as raw text without validation. Defaults to true.
+    pub fn parse_json(&mut self, parse_json: bool) {
+        self.parse_json = parse_json;
+    }
 }
 
 impl Source for SqliteSource
@@ -106,7 +122,15 @@ where
             if !names.is_empty() && !types.is_empty() {
                 success = true;
                 self.names = names;
-                self.schema = types;
+                self.schema = types
+                    .into_iter()
+                    .map(|ty| match ty {
+                        SqliteTypeSystem::JSON(nullable) if !self.parse_json => {
+                            SqliteTypeSystem::Text(nullable)
+                        }
+                        _ => ty,
+                    })
+                    .collect();
                 break;
             }
         }
@@ -184,11 +208,11 @@ impl SourcePartition for SqliteSourcePartition {
     fn prepare(&mut self) -> Result<()> {
         let dialect = SQLiteDialect {};
         self.nrows = match get_limit(&self.query, &dialect)? {
-            None => self
-                .conn
-                .query_row(&count_query(&self.query, &dialect)?[..], [], |row| {
-                    Ok(row.get::<_, i64>(0)? as usize)
-                })?,
+            None => checked_count(self.conn.query_row(
+                &count_query(&self.query, &dialect)?[..],
+                [],
+                |row| row.get::<_, i64>(0),
+            )?)?,
             Some(n) => n,
         };
         Ok(())
@@ -215,6 +239,8 @@ pub struct SqliteSourcePartitionParser<'a> {
     rows: OwningHandle<Box<Statement<'a>>, DummyBox<Rows<'a>>>,
     ncols: usize,
     current_col: usize,
+    // `is_finished` already stepped to the next row, which `next_loc` should not skip
+    row_fetched: bool,
 }
 
 impl<'a> SqliteSourcePartitionParser<'a> {
@@ -224,6 +250,12 @@ impl<'a> SqliteSourcePartitionParser<'a> {
         schema: &[SqliteTypeSystem],
     ) -> Result<Self> {
         let stmt: Statement<'a> = conn.prepare(query)?;
+        if stmt.column_count() != schema.len() {
+            throw!(ConnectorAgentError::ColumnCountMismatch(
+                schema.len(),
+                stmt.column_count()
+            ));
+        }
 
         // Safety: DummyBox borrows the on-heap stmt, which is owned by the OwningHandle.
         // No matter how we move the owning handle (thus the Box<Statment>), the Statement
@@ -236,12 +268,21 @@ impl<'a> SqliteSourcePartitionParser<'a> {
             rows,
             ncols: schema.len(),
             current_col: 0,
+            row_fetched: false,
         })
     }
 
     fn next_loc(&mut self) -> Result<(&Row, usize)> {
         let row: &Row = match self.current_col {
-            0 => (*self.rows).next()?.ok_or_else(|| anyhow!("Sqlite EOF"))?,
+            0 => {
+                let row = if self.row_fetched {
+                    self.row_fetched = false;
+                    (*self.rows).get()
+                } else {
+                    (*self.rows).next()?
+                };
+                row.ok_or_else(|| anyhow!("Sqlite parser is already finished"))?
+            }
             _ => (*self.rows)
                 .get()
                 .ok_or_else(|| anyhow!("Sqlite empty current row"))?,
@@ -254,6 +295,42 @@ impl<'a> SqliteSourcePartitionParser<'a> {
 
 impl<'a> PartitionParser<'a> for SqliteSourcePartitionParser<'a> {
     type TypeSystem = SqliteTypeSystem;
+
+    fn is_finished(&mut self) -> Result<bool> {
+        if self.current_col != 0 {
+            return Ok(false);
+        }
+        if !self.row_fetched {
+            (*self.rows).next()?;
+            self.row_fetched = true;
+        }
+        Ok((*self.rows).get().is_none())
+    }
+}
+
+/// SQLite only checks the type of a value when it is read, and a column may hold values of any
+/// storage class regardless of its declared type. On mismatch, report the storage class of the cell.
+fn get_cell<T: FromSql>(row: &Row, col: usize) -> Result<T> {
+    row.get(col).map_err(|e| match e {
+        rusqlite::Error::InvalidColumnType(_, name, ty) => {
+            ConnectorAgentError::cannot_produce::<T>(Some(format!(
+                "column {} holds a {} value",
+                name,
+                storage_class(ty)
+            )))
+        }
+        e => e.into(),
+    })
+}
+
+fn storage_class(ty: Type) -> &'static str {
+    match ty {
+        Type::Null => "NULL",
+        Type::Integer => "INTEGER",
+        Type::Real => "REAL",
+        Type::Text => "TEXT",
+        Type::Blob => "BLOB",
+    }
 }
 
 macro_rules! impl_produce {
@@ -262,16 +339,14 @@ macro_rules! impl_produce {
             impl<'r, 'a> Produce<'r, $t> for SqliteSourcePartitionParser<'a> {
                 fn produce(&'r mut self) -> Result<$t> {
                     let (row, col) = self.next_loc()?;
-                    let val = row.get(col)?;
-                    Ok(val)
+                    get_cell(row, col)
                 }
             }
 
             impl<'r, 'a> Produce<'r, Option<$t>> for SqliteSourcePartitionParser<'a> {
                 fn produce(&'r mut self) -> Result<Option<$t>> {
                     let (row, col) = self.next_loc()?;
-                    let val = row.get(col)?;
-                    Ok(val)
+                    get_cell(row, col)
                 }
             }
         )+
@@ -290,3 +365,24 @@ impl_produce!(
     NaiveDateTime,
     Vec<u8>,
 );
+
+impl<'r, 'a> Produce<'r, Value> for SqliteSourcePartitionParser<'a> {
+    fn produce(&'r mut self) -> Result<Value> {
+        let (row, col) = self.next_loc()?;
+        let val: String = get_cell(row, col)?;
+        from_str(&val).map_err(|_| ConnectorAgentError::cannot_produce::<Value>(Some(val)))
+    }
+}
+
+impl<'r, 'a> Produce<'r, Option<Value>> for SqliteSourcePartitionParser<'a> {
+    fn produce(&'r mut self) -> Result<Option<Value>> {
+        let (row, col) = self.next_loc()?;
+        let val: Option<String> = get_cell(row, col)?;
+        match val {
+            None => Ok(None),
+            Some(v) => from_str(&v)
+                .map(Some)
+                .map_err(|_| ConnectorAgentError::cannot_produce::<Value>(Some(v))),
+        }
+    }
+}
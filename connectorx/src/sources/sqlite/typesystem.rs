@@ -1,5 +1,6 @@
 use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
 use rusqlite::types::Type;
+use serde_json::Value;
 
 #[derive(Copy, Clone, Debug)]
 pub enum SqliteTypeSystem {
@@ -13,6 +14,7 @@ pub enum SqliteTypeSystem {
     Time(bool),
     Timestamp(bool),
     Blob(bool),
+    JSON(bool),
 }
 
 impl_typesystem! {
@@ -28,6 +30,7 @@ impl_typesystem! {
         { Time => NaiveTime}
         { Timestamp => NaiveDateTime}
         { Blob => Vec<u8>}
+        { JSON => Value}
     }
 }
 
@@ -58,6 +61,8 @@ impl From<(Option<&str>, Type)> for SqliteTypeSystem {
                     "date" => Date(true),
                     "time" => Time(true),
                     "datetime" | "timestamp" => Timestamp(true),
+                    // JSON1 stores json as text, only the declared type tells them apart
+                    "json" => JSON(true),
                     _ if s.contains("int") => Int8(true),
                     _ if s.contains("char") || s.contains("clob") || s.contains("text") => {
                         Text(true)
@@ -3,8 +3,8 @@ use anyhow::anyhow;
 use fehler::{throw, throws};
 use log::{debug, trace};
 use sqlparser::ast::{
-    BinaryOperator, Expr, Function, FunctionArg, Ident, ObjectName, Query, Select, SelectItem,
-    SetExpr, Statement, TableAlias, TableFactor, TableWithJoins, Value,
+    BinaryOperator, DataType, Expr, Function, FunctionArg, Ident, ObjectName, Offset, OffsetRows,
+    Query, Select, SelectItem, SetExpr, Statement, TableAlias, TableFactor, TableWithJoins, Value,
 };
 use sqlparser::dialect::Dialect;
 use sqlparser::parser::Parser;
@@ -208,6 +208,267 @@ pub fn single_col_partition_query<T: Dialect>(
     sql
 }
 
+/// Partition a query by hashing a (e.g. string or uuid) column into `num` buckets, keeping the rows
+/// of bucket `bucket`: `abs(MOD(hash_func(CAST(col AS TEXT)), num)) = bucket`. The predicate cannot
+/// use an index range scan, so every partition scans the whole query.
+#[throws(ConnectorAgentError)]
+pub fn hash_partition_query<T: Dialect>(
+    query: &str,
+    col: &str,
+    hash_func: &str,
+    num: usize,
+    bucket: usize,
+    dialect: &T,
+) -> String {
+    trace!("Incoming query: {}", query);
+    const PART_TMP_TAB_NAME: &str = "CXTMPTAB_PART";
+
+    let mut ast = Parser::parse_sql(dialect, query)?;
+    if ast.len() != 1 {
+        throw!(ConnectorAgentError::SQLQueryNotSupported(query.to_string()));
+    }
+
+    let func = |name: &str, args: Vec<Expr>| {
+        Expr::Function(Function {
+            name: ObjectName(vec![Ident {
+                value: name.to_string(),
+                quote_style: None,
+            }]),
+            args: args.into_iter().map(FunctionArg::Unnamed).collect(),
+            over: None,
+            distinct: false,
+        })
+    };
+
+    let ast_part: Statement;
+
+    match &mut ast[0] {
+        Statement::Query(q) => match &mut q.body {
+            SetExpr::Select(_select) => {
+                let hash = func(
+                    hash_func,
+                    vec![Expr::Cast {
+                        expr: Box::new(Expr::CompoundIdentifier(vec![
+                            Ident {
+                                value: PART_TMP_TAB_NAME.to_string(),
+                                quote_style: None,
+                            },
+                            Ident {
+                                value: col.to_string(),
+                                quote_style: None,
+                            },
+                        ])),
+                        data_type: DataType::Text,
+                    }],
+                );
+                // the hash can be negative, take abs after MOD so that it cannot overflow
+                let selection = Expr::BinaryOp {
+                    left: Box::new(func(
+                        "abs",
+                        vec![func(
+                            "MOD",
+                            vec![hash, Expr::Value(Value::Number(num.to_string(), false))],
+                        )],
+                    )),
+                    op: BinaryOperator::Eq,
+                    right: Box::new(Expr::Value(Value::Number(bucket.to_string(), false))),
+                };
+
+                ast_part = wrap_query(
+                    q.clone(),
+                    vec![SelectItem::Wildcard],
+                    Some(selection),
+                    PART_TMP_TAB_NAME.to_string(),
+                );
+            }
+            _ => throw!(ConnectorAgentError::SQLQueryNotSupported(query.to_string())),
+        },
+        _ => throw!(ConnectorAgentError::SQLQueryNotSupported(query.to_string())),
+    };
+
+    let sql = format!("{}", ast_part);
+    debug!("Transformed hash partition query: {}", sql);
+    sql
+}
+
+/// Partition a query by row position. The rows of an unordered query have no stable order,
+/// so partitions could overlap or miss rows: the query must carry an ORDER BY, whose keys
+/// should be unique for the same reason. Only the presence of the ORDER BY is checked.
+#[throws(ConnectorAgentError)]
+pub fn offset_partition_query<T: Dialect>(
+    query: &str,
+    offset: usize,
+    limit: usize,
+    dialect: &T,
+) -> String {
+    trace!("Incoming query: {}", query);
+
+    let mut ast = Parser::parse_sql(dialect, query)?;
+    if ast.len() != 1 {
+        throw!(ConnectorAgentError::SQLQueryNotSupported(query.to_string()));
+    }
+
+    match &mut ast[0] {
+        Statement::Query(q) => {
+            if q.order_by.is_empty() {
+                throw!(ConnectorAgentError::SQLQueryNotOrdered(query.to_string()));
+            }
+            if q.limit.is_some() || q.offset.is_some() || q.fetch.is_some() {
+                throw!(ConnectorAgentError::SQLQueryPartitionNotSupported(
+                    query.to_string()
+                ));
+            }
+            q.limit = Some(Expr::Value(Value::Number(limit.to_string(), false)));
+            q.offset = Some(Offset {
+                value: Expr::Value(Value::Number(offset.to_string(), false)),
+                rows: OffsetRows::None,
+            });
+        }
+        _ => throw!(ConnectorAgentError::SQLQueryNotSupported(query.to_string())),
+    };
+
+    let sql = format!("{}", ast[0]);
+    debug!("Transformed offset partition query: {}", sql);
+    sql
+}
+
+const WATERMARK_TMP_TAB_NAME: &str = "CXTMPTAB_WATERMARK";
+
+/// `lower < col AND col <= upper` on the watermark table, skipping the missing bounds.
+/// The bounds are quoted so that the database casts them into the type of `col`.
+fn watermark_selection(col: &str, lower: Option<&str>, upper: Option<&str>) -> Option<Expr> {
+    let col = Expr::CompoundIdentifier(vec![
+        Ident {
+            value: WATERMARK_TMP_TAB_NAME.to_string(),
+            quote_style: None,
+        },
+        Ident {
+            value: col.to_string(),
+            quote_style: None,
+        },
+    ]);
+
+    let lb = lower.map(|lower| Expr::BinaryOp {
+        left: Box::new(col.clone()),
+        op: BinaryOperator::Gt,
+        right: Box::new(Expr::Value(Value::SingleQuotedString(lower.to_string()))),
+    });
+    let ub = upper.map(|upper| Expr::BinaryOp {
+        left: Box::new(col.clone()),
+        op: BinaryOperator::LtEq,
+        right: Box::new(Expr::Value(Value::SingleQuotedString(upper.to_string()))),
+    });
+
+    match (lb, ub) {
+        (Some(lb), Some(ub)) => Some(Expr::BinaryOp {
+            left: Box::new(lb),
+            op: BinaryOperator::And,
+            right: Box::new(ub),
+        }),
+        (lb, ub) => lb.or(ub),
+    }
+}
+
+/// Read the rows of `query` whose watermark column `col` is in `(lower, upper]`.
+#[throws(ConnectorAgentError)]
+pub fn watermark_query<T: Dialect>(
+    query: &str,
+    col: &str,
+    lower: Option<&str>,
+    upper: Option<&str>,
+    dialect: &T,
+) -> String {
+    trace!("Incoming query: {}", query);
+
+    let mut ast = Parser::parse_sql(dialect, query)?;
+    if ast.len() != 1 {
+        throw!(ConnectorAgentError::SQLQueryNotSupported(query.to_string()));
+    }
+
+    let ast_watermark: Statement;
+
+    match &mut ast[0] {
+        Statement::Query(q) => match &mut q.body {
+            SetExpr::Select(_select) => {
+                ast_watermark = wrap_query(
+                    q.clone(),
+                    vec![SelectItem::Wildcard],
+                    watermark_selection(col, lower, upper),
+                    WATERMARK_TMP_TAB_NAME.to_string(),
+                );
+            }
+            _ => throw!(ConnectorAgentError::SQLQueryNotSupported(query.to_string())),
+        },
+        _ => throw!(ConnectorAgentError::SQLQueryNotSupported(query.to_string())),
+    };
+
+    let sql = format!("{}", ast_watermark);
+    debug!("Transformed watermark query: {}", sql);
+    sql
+}
+
+/// Get the largest watermark column `col` greater than `lower`, casted to text so that
+/// it can be handed back as the `lower` bound of the next run regardless of its type.
+#[throws(ConnectorAgentError)]
+pub fn watermark_max_query<T: Dialect>(
+    query: &str,
+    col: &str,
+    lower: Option<&str>,
+    dialect: &T,
+) -> String {
+    trace!("Incoming query: {}", query);
+
+    let mut ast = Parser::parse_sql(dialect, query)?;
+    if ast.len() != 1 {
+        throw!(ConnectorAgentError::SQLQueryNotSupported(query.to_string()));
+    }
+
+    let ast_max: Statement;
+
+    match &mut ast[0] {
+        Statement::Query(q) => {
+            q.order_by = vec![];
+            match &mut q.body {
+                SetExpr::Select(_select) => {
+                    let projection = vec![SelectItem::UnnamedExpr(Expr::Cast {
+                        expr: Box::new(Expr::Function(Function {
+                            name: ObjectName(vec![Ident {
+                                value: "max".to_string(),
+                                quote_style: None,
+                            }]),
+                            args: vec![FunctionArg::Unnamed(Expr::CompoundIdentifier(vec![
+                                Ident {
+                                    value: WATERMARK_TMP_TAB_NAME.to_string(),
+                                    quote_style: None,
+                                },
+                                Ident {
+                                    value: col.to_string(),
+                                    quote_style: None,
+                                },
+                            ]))],
+                            over: None,
+                            distinct: false,
+                        })),
+                        data_type: DataType::Text,
+                    })];
+                    ast_max = wrap_query(
+                        q.clone(),
+                        projection,
+                        watermark_selection(col, lower, None),
+                        WATERMARK_TMP_TAB_NAME.to_string(),
+                    );
+                }
+                _ => throw!(ConnectorAgentError::SQLQueryNotSupported(query.to_string())),
+            }
+        }
+        _ => throw!(ConnectorAgentError::SQLQueryNotSupported(query.to_string())),
+    };
+
+    let sql = format!("{}", ast_max);
+    debug!("Transformed watermark max query: {}", sql);
+    sql
+}
+
 #[throws(ConnectorAgentError)]
 pub fn get_partition_range_query<T: Dialect>(query: &str, col: &str, dialect: &T) -> String {
     trace!("Incoming query: {}", query);
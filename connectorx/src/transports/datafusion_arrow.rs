@@ -0,0 +1,18 @@
+use crate::destinations::arrow::ArrowDestination;
+use crate::dummy_typesystem::DummyTypeSystem;
+use crate::sources::datafusion::DataFusionSource;
+use crate::typesystem::TypeConversion;
+
+pub struct DataFusionArrowTransport;
+
+impl_transport!(
+    name = DataFusionArrowTransport,
+    systems = DummyTypeSystem => DummyTypeSystem,
+    route = DataFusionSource => ArrowDestination,
+    mappings = {
+        { F64[f64]                => F64[f64]                | conversion all}
+        { I64[i64]                => I64[i64]                | conversion all}
+        { Bool[bool]              => Bool[bool]              | conversion all}
+        { String[String]          => String[String]          | conversion all}
+    }
+);
@@ -1,13 +1,21 @@
 mod csv_arrow;
 mod csv_memory;
+#[cfg(feature = "datafusion")]
+mod datafusion_arrow;
 mod dummy_arrow;
 mod dummy_memory;
 mod postgres_arrow;
+#[cfg(feature = "avro")]
+mod postgres_avro;
 mod postgres_memory;
 
 pub use csv_arrow::CSVArrowTransport;
 pub use csv_memory::CSVMemoryTransport;
+#[cfg(feature = "datafusion")]
+pub use datafusion_arrow::DataFusionArrowTransport;
 pub use dummy_arrow::DummyArrowTransport;
 pub use dummy_memory::DummyMemoryTransport;
 pub use postgres_arrow::PostgresArrowTransport;
+#[cfg(feature = "avro")]
+pub use postgres_avro::PostgresAvroTransport;
 pub use postgres_memory::PostgresMemoryTransport;
@@ -1,8 +1,11 @@
 use crate::destinations::arrow::ArrowDestination;
 use crate::dummy_typesystem::DummyTypeSystem;
+use crate::point::Point;
+use crate::range::Range;
 use crate::sources::postgres::{Binary, PostgresSource, PostgresTypeSystem};
 use crate::typesystem::TypeConversion;
-use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use chrono::{Date, DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use rust_decimal::Decimal;
 use uuid::Uuid;
 
 pub struct PostgresArrowTransport;
@@ -14,6 +17,7 @@ impl_transport!(
     mappings = {
         { Float4[f32]                => F64[f64]                | conversion all }
         { Float8[f64]                => F64[f64]                | conversion all }
+        { Numeric[Decimal]           => Decimal[Decimal]        | conversion all }
         { Int2[i16]                  => I64[i64]                | conversion all }
         { Int4[i32]                  => I64[i64]                | conversion all }
         { Int8[i64]                  => I64[i64]                | conversion all }
@@ -23,9 +27,13 @@ impl_transport!(
         { VarChar[&'r str]           => String[String]          | conversion none }
         { Timestamp[NaiveDateTime]   => DateTime[DateTime<Utc>] | conversion half }
         { TimestampTz[DateTime<Utc>] => DateTime[DateTime<Utc>] | conversion all }
-        { Date[NaiveDate]            => DateTime[DateTime<Utc>] | conversion half }
+        { Date[NaiveDate]            => Date[Date<Utc>]         | conversion half }
         { UUID[Uuid]                 => String[String]          | conversion half }
+        { ByteA[Vec<u8>]             => Bytes[Vec<u8>]          | conversion all }
         { Char[&'r str]              => String[String]          | conversion none}
+        { Int4Range[Range<i32>]      => I64Range[Range<i64>]    | conversion half }
+        { DateRange[Range<NaiveDate>] => DateRange[Range<NaiveDate>] | conversion all }
+        { Point[Point]               => Point[Point]            | conversion all }
         // { Time[NaiveTime]            => String[String]          | conversion half }
     }
 );
@@ -54,8 +62,14 @@ impl TypeConversion<NaiveDateTime, DateTime<Utc>> for PostgresArrowTransport {
     }
 }
 
-impl TypeConversion<NaiveDate, DateTime<Utc>> for PostgresArrowTransport {
-    fn convert(val: NaiveDate) -> DateTime<Utc> {
-        DateTime::from_utc(val.and_hms(0, 0, 0), Utc)
+impl TypeConversion<NaiveDate, Date<Utc>> for PostgresArrowTransport {
+    fn convert(val: NaiveDate) -> Date<Utc> {
+        Date::from_utc(val, Utc)
+    }
+}
+
+impl TypeConversion<Range<i32>, Range<i64>> for PostgresArrowTransport {
+    fn convert(val: Range<i32>) -> Range<i64> {
+        val.map(i64::from)
     }
 }
@@ -0,0 +1,75 @@
+use crate::destinations::avro::AvroDestination;
+use crate::dummy_typesystem::DummyTypeSystem;
+use crate::point::Point;
+use crate::range::Range;
+use crate::sources::postgres::{Binary, PostgresSource, PostgresTypeSystem};
+use crate::typesystem::TypeConversion;
+use chrono::{Date, DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+pub struct PostgresAvroTransport;
+
+impl_transport!(
+    name = PostgresAvroTransport,
+    systems = PostgresTypeSystem => DummyTypeSystem,
+    route = PostgresSource<Binary> => AvroDestination,
+    mappings = {
+        { Float4[f32]                => F64[f64]                | conversion all }
+        { Float8[f64]                => F64[f64]                | conversion all }
+        { Numeric[Decimal]           => Decimal[Decimal]        | conversion all }
+        { Int2[i16]                  => I64[i64]                | conversion all }
+        { Int4[i32]                  => I64[i64]                | conversion all }
+        { Int8[i64]                  => I64[i64]                | conversion all }
+        { Bool[bool]                 => Bool[bool]              | conversion all  }
+        { Text[&'r str]              => String[String]          | conversion half }
+        { BpChar[&'r str]            => String[String]          | conversion none }
+        { VarChar[&'r str]           => String[String]          | conversion none }
+        { Timestamp[NaiveDateTime]   => DateTime[DateTime<Utc>] | conversion half }
+        { TimestampTz[DateTime<Utc>] => DateTime[DateTime<Utc>] | conversion all }
+        { Date[NaiveDate]            => Date[Date<Utc>]         | conversion half }
+        { UUID[Uuid]                 => String[String]          | conversion half }
+        { ByteA[Vec<u8>]             => Bytes[Vec<u8>]          | conversion all }
+        { Char[&'r str]              => String[String]          | conversion none}
+        { Int4Range[Range<i32>]      => I64Range[Range<i64>]    | conversion half }
+        { DateRange[Range<NaiveDate>] => DateRange[Range<NaiveDate>] | conversion all }
+        { Point[Point]               => Point[Point]            | conversion all }
+        // { Time[NaiveTime]            => String[String]          | conversion half }
+    }
+);
+
+impl TypeConversion<Uuid, String> for PostgresAvroTransport {
+    fn convert(val: Uuid) -> String {
+        val.to_string()
+    }
+}
+
+impl TypeConversion<NaiveTime, String> for PostgresAvroTransport {
+    fn convert(val: NaiveTime) -> String {
+        val.to_string()
+    }
+}
+
+impl<'r> TypeConversion<&'r str, String> for PostgresAvroTransport {
+    fn convert(val: &'r str) -> String {
+        val.to_string()
+    }
+}
+
+impl TypeConversion<NaiveDateTime, DateTime<Utc>> for PostgresAvroTransport {
+    fn convert(val: NaiveDateTime) -> DateTime<Utc> {
+        DateTime::from_utc(val, Utc)
+    }
+}
+
+impl TypeConversion<NaiveDate, Date<Utc>> for PostgresAvroTransport {
+    fn convert(val: NaiveDate) -> Date<Utc> {
+        Date::from_utc(val, Utc)
+    }
+}
+
+impl TypeConversion<Range<i32>, Range<i64>> for PostgresAvroTransport {
+    fn convert(val: Range<i32>) -> Range<i64> {
+        val.map(i64::from)
+    }
+}
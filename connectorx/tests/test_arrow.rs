@@ -1,9 +1,18 @@
-use arrow::array::{BooleanArray, Float64Array, Int64Array, StringArray};
-use arrow::record_batch::RecordBatch;
+use arrow::array::{
+    make_array_from_raw, Array, ArrayBuilder, BooleanArray, DictionaryArray, Float64Array,
+    Int64Array, StringArray,
+};
+use arrow::datatypes::{DataType as ArrowDataType, Field, Int32Type, Int8Type, Schema, SchemaRef};
+use arrow::error::Result as ArrowResult;
+use arrow::record_batch::{RecordBatch, RecordBatchReader};
+use chrono::{DateTime, TimeZone, Utc};
 use connectorx::{
-    destinations::arrow::ArrowDestination, sources::dummy::DummySource,
-    transports::DummyArrowTransport, Dispatcher, DummyTypeSystem,
+    destinations::arrow::{unify_dictionaries, ArrowAssoc, ArrowDestination, NullSentinel},
+    sources::dummy::DummySource,
+    transports::DummyArrowTransport,
+    ConnectorAgentError, DataOrder, Destination, DestinationPartition, Dispatcher, DummyTypeSystem,
 };
+use std::sync::Arc;
 
 #[test]
 fn test_arrow() {
@@ -110,3 +119,326 @@ fn test_arrow() {
         }
     }
 }
+
+#[test]
+fn test_skip_empty_partitions() {
+    let schema = [DummyTypeSystem::I64(false), DummyTypeSystem::F64(true)];
+    let queries = ["4,2", "0,2", "7,2", "0,2"];
+    let mut destination = ArrowDestination::new();
+    let mut dispatcher = Dispatcher::<_, _, DummyArrowTransport>::new(
+        DummySource::new(&["a", "b"], &schema),
+        &mut destination,
+        &queries,
+    );
+    dispatcher.skip_empty_partitions(true);
+    dispatcher.run().expect("run dispatcher");
+
+    let records: Vec<RecordBatch> = destination
+        .finish(vec!["c0".to_string(), "c1".to_string()])
+        .unwrap();
+    assert_eq!(2, records.len());
+    assert_eq!(4, records[0].num_rows());
+    assert_eq!(7, records[1].num_rows());
+    assert!(records[1]
+        .column(0)
+        .as_any()
+        .downcast_ref::<Int64Array>()
+        .unwrap()
+        .eq(&Int64Array::from(vec![0, 1, 2, 3, 4, 5, 6])));
+}
+
+#[test]
+fn test_null_sentinel() {
+    let mut destination = ArrowDestination::new();
+    destination.null_sentinel("a", NullSentinel::I64(-1));
+    destination
+        .allocate(
+            4,
+            &["a", "b"],
+            &[DummyTypeSystem::I64(true), DummyTypeSystem::F64(true)],
+            DataOrder::RowMajor,
+        )
+        .unwrap();
+
+    let mut partitions = destination.partition(&[4]).unwrap();
+    for (a, b) in vec![
+        (Some(1), None),
+        (None, Some(2.0)),
+        (Some(3), Some(3.0)),
+        (None, None),
+    ] {
+        partitions[0].write::<Option<i64>>(a).unwrap();
+        partitions[0].write::<Option<f64>>(b).unwrap();
+    }
+    std::mem::drop(partitions);
+
+    let records: Vec<RecordBatch> = destination
+        .finish(vec!["a".to_string(), "b".to_string()])
+        .unwrap();
+    assert_eq!(1, records.len());
+
+    let schema = records[0].schema();
+    assert!(!schema.field(0).is_nullable());
+    assert!(schema.field(1).is_nullable());
+
+    let col = records[0].column(0);
+    assert_eq!(0, col.null_count());
+    assert!(col.data().null_buffer().is_none());
+    assert!(col
+        .as_any()
+        .downcast_ref::<Int64Array>()
+        .unwrap()
+        .eq(&Int64Array::from(vec![1, -1, 3, -1])));
+    assert_eq!(2, records[0].column(1).null_count());
+}
+
+#[test]
+fn test_null_sentinel_type_mismatch() {
+    let mut destination = ArrowDestination::new();
+    destination.null_sentinel("a", NullSentinel::String("".into()));
+    assert!(destination
+        .allocate(
+            4,
+            &["a"],
+            &[DummyTypeSystem::I64(true)],
+            DataOrder::RowMajor,
+        )
+        .is_err());
+}
+
+#[test]
+fn test_finish_arrays() {
+    let schema = [
+        DummyTypeSystem::I64(false),
+        DummyTypeSystem::String(true),
+        DummyTypeSystem::Bool(true),
+    ];
+    let queries = ["4,3", "7,3"];
+    let mut destination = ArrowDestination::new();
+    let dispatcher = Dispatcher::<_, _, DummyArrowTransport>::new(
+        DummySource::new(&["a", "b", "c"], &schema),
+        &mut destination,
+        &queries,
+    );
+    dispatcher.run().expect("run dispatcher");
+
+    let (arrow_schema, columns) = destination
+        .finish_arrays(vec!["a".to_string(), "b".to_string(), "c".to_string()])
+        .unwrap();
+    assert_eq!(3, columns.len());
+    for (field, column) in arrow_schema.fields().iter().zip(&columns) {
+        assert_eq!(11, column.len());
+        assert_eq!(field.data_type(), column.data_type());
+    }
+    assert!(columns[0]
+        .as_any()
+        .downcast_ref::<Int64Array>()
+        .unwrap()
+        .eq(&Int64Array::from(vec![0, 1, 2, 3, 0, 1, 2, 3, 4, 5, 6])));
+}
+
+fn drain<R: RecordBatchReader>(reader: R) -> (SchemaRef, Vec<RecordBatch>) {
+    let schema = reader.schema();
+    let batches = reader.collect::<ArrowResult<Vec<_>>>().unwrap();
+    (schema, batches)
+}
+
+#[test]
+fn test_record_batch_reader() {
+    let schema = [DummyTypeSystem::I64(false), DummyTypeSystem::F64(true)];
+    let queries = ["4,2", "7,2"];
+    let mut destination = ArrowDestination::new();
+    let dispatcher = Dispatcher::<_, _, DummyArrowTransport>::new(
+        DummySource::new(&["a", "b"], &schema),
+        &mut destination,
+        &queries,
+    );
+    dispatcher.run().expect("run dispatcher");
+
+    let reader = destination
+        .record_batch_reader(vec!["a".to_string(), "b".to_string()])
+        .unwrap();
+    let (arrow_schema, batches) = drain(reader);
+    assert_eq!(2, arrow_schema.fields().len());
+    assert_eq!(2, batches.len());
+    assert_eq!(4, batches[0].num_rows());
+    assert_eq!(7, batches[1].num_rows());
+    assert_eq!(arrow_schema, batches[1].schema());
+    assert!(batches[1]
+        .column(0)
+        .as_any()
+        .downcast_ref::<Int64Array>()
+        .unwrap()
+        .eq(&Int64Array::from(vec![0, 1, 2, 3, 4, 5, 6])));
+}
+
+#[test]
+fn test_ffi_reader() {
+    let schema = [
+        DummyTypeSystem::I64(false),
+        DummyTypeSystem::F64(true),
+        DummyTypeSystem::String(true),
+    ];
+    let queries = ["4,3", "7,3"];
+    let headers = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+    let run = || {
+        let mut destination = ArrowDestination::new();
+        let dispatcher = Dispatcher::<_, _, DummyArrowTransport>::new(
+            DummySource::new(&["a", "b", "c"], &schema),
+            &mut destination,
+            &queries,
+        );
+        dispatcher.run().expect("run dispatcher");
+        destination
+    };
+    let expected = run().finish(headers.clone()).unwrap();
+
+    let exported = run()
+        .ffi_reader(headers)
+        .unwrap()
+        .collect::<connectorx::Result<Vec<_>>>()
+        .unwrap();
+    assert_eq!(expected.len(), exported.len());
+    for (batch, ffi_batch) in expected.iter().zip(exported) {
+        assert_eq!(batch.schema(), ffi_batch.schema());
+        assert_eq!(batch.num_columns(), ffi_batch.num_columns());
+        let raw = unsafe { ffi_batch.export() };
+        for (i, (array, schema)) in raw.into_iter().enumerate() {
+            let imported = unsafe { make_array_from_raw(array, schema) }.unwrap();
+            assert_eq!(batch.column(i).data(), imported.data());
+        }
+    }
+}
+
+fn check_field_matches_builder<T: ArrowAssoc>(values: Vec<T>, nullable: bool) {
+    let mut builder = T::builder(values.len());
+    for v in values {
+        T::append(&mut builder, v).unwrap();
+    }
+    let array = ArrayBuilder::finish(&mut builder);
+    let field = T::field("a");
+    assert_eq!(nullable, field.is_nullable());
+
+    let batch = RecordBatch::try_new(Arc::new(Schema::new(vec![field])), vec![array]).unwrap();
+    assert_eq!(
+        batch.column(0).data_type(),
+        batch.schema().field(0).data_type()
+    );
+}
+
+#[test]
+fn test_numeric_fields_match_builders() {
+    check_field_matches_builder::<i32>(vec![1, 2, 3], false);
+    check_field_matches_builder::<Option<i32>>(vec![Some(1), None], true);
+    check_field_matches_builder::<i64>(vec![1, 2, 3], false);
+    check_field_matches_builder::<Option<i64>>(vec![Some(1), None], true);
+    check_field_matches_builder::<f64>(vec![1.0, 2.0], false);
+    check_field_matches_builder::<Option<f64>>(vec![Some(1.0), None], true);
+}
+
+#[test]
+fn test_timestamp_out_of_nanosecond_range() {
+    let mut builder = <DateTime<Utc> as ArrowAssoc>::builder(2);
+    let in_range = Utc.ymd(2262, 4, 11).and_hms(0, 0, 0);
+    <DateTime<Utc> as ArrowAssoc>::append(&mut builder, in_range).unwrap();
+    let array = builder.finish();
+    assert_eq!(in_range.timestamp_nanos(), array.value(0));
+
+    for year in [1500, 3000].iter() {
+        let out_of_range = Utc.ymd(*year, 1, 1).and_hms(0, 0, 0);
+        assert!(matches!(
+            <DateTime<Utc> as ArrowAssoc>::append(&mut builder, out_of_range),
+            Err(ConnectorAgentError::TimestampOutOfRange(_))
+        ));
+        assert!(
+            <Option<DateTime<Utc>> as ArrowAssoc>::append(&mut builder, Some(out_of_range))
+                .is_err()
+        );
+    }
+}
+
+#[test]
+fn test_unify_dictionaries() {
+    let schema = Arc::new(Schema::new(vec![Field::new(
+        "city",
+        ArrowDataType::Dictionary(
+            Box::new(ArrowDataType::Int32),
+            Box::new(ArrowDataType::Utf8),
+        ),
+        true,
+    )]));
+    let batch = |cities: Vec<Option<&str>>| {
+        let array: DictionaryArray<Int32Type> = cities.into_iter().collect();
+        RecordBatch::try_new(schema.clone(), vec![Arc::new(array)]).unwrap()
+    };
+    // the partitions share "Seattle", which has a different key in each of them
+    let batches = vec![
+        batch(vec![
+            Some("Vancouver"),
+            Some("Seattle"),
+            None,
+            Some("Vancouver"),
+        ]),
+        batch(vec![Some("Seattle"), Some("Toronto")]),
+    ];
+
+    let unified = unify_dictionaries(batches).unwrap();
+    assert_eq!(2, unified.len());
+
+    let dicts: Vec<_> = unified
+        .iter()
+        .map(|rb| {
+            rb.column(0)
+                .as_any()
+                .downcast_ref::<DictionaryArray<Int32Type>>()
+                .unwrap()
+                .values()
+        })
+        .collect();
+    let values = dicts[0].as_any().downcast_ref::<StringArray>().unwrap();
+    assert_eq!(3, values.len());
+    assert_eq!(
+        vec!["Vancouver", "Seattle", "Toronto"],
+        (0..values.len())
+            .map(|i| values.value(i))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(dicts[0].data(), dicts[1].data());
+
+    let decoded: Vec<Vec<Option<&str>>> = unified
+        .iter()
+        .map(|rb| {
+            let array = rb
+                .column(0)
+                .as_any()
+                .downcast_ref::<DictionaryArray<Int32Type>>()
+                .unwrap();
+            let keys = array.keys_array();
+            (0..keys.len())
+                .map(|i| match keys.is_null(i) {
+                    true => None,
+                    false => Some(values.value(keys.value(i) as usize)),
+                })
+                .collect()
+        })
+        .collect();
+    assert_eq!(
+        vec![
+            vec![Some("Vancouver"), Some("Seattle"), None, Some("Vancouver")],
+            vec![Some("Seattle"), Some("Toronto")],
+        ],
+        decoded
+    );
+}
+
+#[test]
+fn test_unify_dictionaries_unsupported_type() {
+    let schema = Arc::new(Schema::new(vec![Field::new(
+        "city",
+        ArrowDataType::Dictionary(Box::new(ArrowDataType::Int8), Box::new(ArrowDataType::Utf8)),
+        true,
+    )]));
+    let array: DictionaryArray<Int8Type> = vec![Some("Vancouver"), None].into_iter().collect();
+    let batch = RecordBatch::try_new(schema, vec![Arc::new(array)]).unwrap();
+    assert!(unify_dictionaries(vec![batch]).is_err());
+}
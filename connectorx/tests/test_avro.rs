@@ -0,0 +1,145 @@
+#![cfg(feature = "avro")]
+
+use avro_rs::{types::Value, Reader};
+use connectorx::{
+    destinations::{avro::AvroDestination, DecimalPrecision},
+    sources::postgres::{Binary, PostgresSource},
+    transports::PostgresAvroTransport,
+    Dispatcher,
+};
+use std::env;
+use std::fs::{self, File};
+
+#[test]
+fn test_postgres_avro() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let dburl = env::var("POSTGRES_URL").unwrap();
+    let queries = ["select test_int, test_str, test_float, test_bool, \
+         '2021-01-02 03:04:05'::timestamp as test_ts, 1.25::numeric as test_dec \
+         from test_table where test_int < 2 order by test_int"];
+    let source = PostgresSource::<Binary>::new(&dburl, 1).unwrap();
+    let path = env::temp_dir().join("connectorx_test_postgres_avro.avro");
+    let mut destination = AvroDestination::new(File::create(&path).unwrap());
+    let dispatcher =
+        Dispatcher::<_, _, PostgresAvroTransport>::new(source, &mut destination, &queries);
+    dispatcher.run().expect("run dispatcher");
+    destination.finish().unwrap();
+
+    let file = fs::read(&path).unwrap();
+    let records = Reader::new(&file[..])
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(2, records.len());
+
+    let fields = |record: &Value| match record {
+        Value::Record(fields) => fields.clone(),
+        _ => panic!("not a record: {:?}", record),
+    };
+    let some = |value: Value| Value::Union(Box::new(value));
+
+    // test_int 0: a test_str of "a", a test_float of 3.1 and a null test_bool
+    let row = fields(&records[0]);
+    let names: Vec<_> = row.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(
+        vec![
+            "test_int",
+            "test_str",
+            "test_float",
+            "test_bool",
+            "test_ts",
+            "test_dec"
+        ],
+        names
+    );
+    assert_eq!(some(Value::Long(0)), row[0].1);
+    assert_eq!(some(Value::String("a".to_string())), row[1].1);
+    assert_eq!(some(Value::Double(3.1)), row[2].1);
+    assert_eq!(Value::Union(Box::new(Value::Null)), row[3].1);
+    assert_eq!(
+        some(Value::TimestampMicros(1_609_556_645_000_000)),
+        row[4].1
+    );
+    // 1.25 at a scale of 10
+    let dec = 12_500_000_000i128.to_be_bytes().to_vec();
+    assert_eq!(some(Value::Decimal(dec.into())), row[5].1);
+
+    // test_int 1: a test_str of "str1" and a test_bool of true
+    let row = fields(&records[1]);
+    assert_eq!(some(Value::Long(1)), row[0].1);
+    assert_eq!(some(Value::String("str1".to_string())), row[1].1);
+    assert_eq!(some(Value::Boolean(true)), row[3].1);
+}
+
+#[test]
+fn test_postgres_avro_partitions() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let dburl = env::var("POSTGRES_URL").unwrap();
+    let queries = [
+        "select test_int from test_table where test_int < 2",
+        "select test_int from test_table where test_int >= 2",
+    ];
+    let source = PostgresSource::<Binary>::new(&dburl, 2).unwrap();
+    let path = env::temp_dir().join("connectorx_test_postgres_avro_partitions.avro");
+    let mut destination = AvroDestination::new(File::create(&path).unwrap());
+    let dispatcher =
+        Dispatcher::<_, _, PostgresAvroTransport>::new(source, &mut destination, &queries);
+    dispatcher.run().expect("run dispatcher");
+    destination.finish().unwrap();
+
+    // each partition is a block of the same file
+    let file = fs::read(&path).unwrap();
+    let mut ints: Vec<_> = Reader::new(&file[..])
+        .unwrap()
+        .map(|record| match record.unwrap() {
+            Value::Record(fields) => match &fields[0].1 {
+                Value::Union(value) => match **value {
+                    Value::Long(i) => i,
+                    ref value => panic!("not a long: {:?}", value),
+                },
+                value => panic!("not a union: {:?}", value),
+            },
+            record => panic!("not a record: {:?}", record),
+        })
+        .collect();
+    ints.sort();
+    assert_eq!(vec![0, 1, 2, 3, 4, 1314], ints);
+}
+
+#[test]
+fn test_postgres_avro_decimal_precision() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let dburl = env::var("POSTGRES_URL").unwrap();
+    let queries = ["select 1.25::numeric as test_dec"];
+    let path = env::temp_dir().join("connectorx_test_postgres_avro_decimal.avro");
+    let read = |precision: usize, scale: usize| {
+        let source = PostgresSource::<Binary>::new(&dburl, 1).unwrap();
+        let mut destination = AvroDestination::new(File::create(&path).unwrap());
+        destination.decimal_precision("test_dec", DecimalPrecision { precision, scale });
+        let dispatcher =
+            Dispatcher::<_, _, PostgresAvroTransport>::new(source, &mut destination, &queries);
+        dispatcher.run()?;
+        destination.finish()
+    };
+
+    read(4, 2).expect("read at a scale of 2");
+    let file = fs::read(&path).unwrap();
+    let records = Reader::new(&file[..])
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let dec = 125i128.to_be_bytes().to_vec();
+    assert_eq!(
+        Value::Record(vec![(
+            "test_dec".to_string(),
+            Value::Union(Box::new(Value::Decimal(dec.into())))
+        )]),
+        records[0]
+    );
+
+    // 1.25 loses a digit at a scale of 1
+    assert!(read(4, 1).is_err());
+}
@@ -0,0 +1,105 @@
+use arrow::record_batch::RecordBatch;
+use connectorx::{
+    cache::QueryCache, destinations::arrow::ArrowDestination, sources::dummy::DummySource,
+    transports::DummyArrowTransport, Dispatcher, DummyTypeSystem, Result,
+};
+use std::cell::Cell;
+use std::time::Duration;
+
+fn read(query: &str, count: &Cell<usize>) -> Result<Vec<RecordBatch>> {
+    count.set(count.get() + 1);
+    let schema = [DummyTypeSystem::I64(false)];
+    let mut destination = ArrowDestination::new();
+    let dispatcher = Dispatcher::<_, _, DummyArrowTransport>::new(
+        DummySource::new(&["a"], &schema),
+        &mut destination,
+        &[query],
+    );
+    dispatcher.run()?;
+    destination.finish(vec!["a".to_string()])
+}
+
+#[test]
+fn test_cache_hit() {
+    let count = Cell::new(0);
+    let mut cache = QueryCache::new(1 << 20, Duration::from_secs(60));
+
+    let first = cache
+        .get_or_read("dummy", &["4,1"], &[], || read("4,1", &count))
+        .unwrap();
+    let second = cache
+        .get_or_read("dummy", &[" 4,1\n"], &[], || read("4,1", &count))
+        .unwrap();
+    assert_eq!(1, count.get());
+    assert_eq!(first[0].num_rows(), second[0].num_rows());
+
+    cache
+        .get_or_read("dummy", &["5,1"], &[], || read("5,1", &count))
+        .unwrap();
+    assert_eq!(2, count.get());
+}
+
+#[test]
+fn test_cache_expired() {
+    let count = Cell::new(0);
+    let mut cache = QueryCache::new(1 << 20, Duration::from_secs(0));
+
+    cache
+        .get_or_read("dummy", &["4,1"], &[], || read("4,1", &count))
+        .unwrap();
+    cache
+        .get_or_read("dummy", &["4,1"], &[], || read("4,1", &count))
+        .unwrap();
+    assert_eq!(2, count.get());
+}
+
+#[test]
+fn test_cache_bounded() {
+    let count = Cell::new(0);
+    let mut cache = QueryCache::new(0, Duration::from_secs(60));
+
+    cache
+        .get_or_read("dummy", &["4,1"], &[], || read("4,1", &count))
+        .unwrap();
+    cache
+        .get_or_read("dummy", &["4,1"], &[], || read("4,1", &count))
+        .unwrap();
+    assert_eq!(2, count.get());
+}
+
+#[test]
+fn test_cache_keeps_literals() {
+    let count = Cell::new(0);
+    let mut cache = QueryCache::new(1 << 20, Duration::from_secs(60));
+
+    cache
+        .get_or_read("dummy", &["SELECT 'a  b'"], &[], || read("4,1", &count))
+        .unwrap();
+    cache
+        .get_or_read("dummy", &["select\n 'a  b'"], &[], || read("4,1", &count))
+        .unwrap();
+    assert_eq!(1, count.get());
+
+    // the whitespace inside the literal is part of the query
+    cache
+        .get_or_read("dummy", &["SELECT 'a b'"], &[], || read("4,1", &count))
+        .unwrap();
+    assert_eq!(2, count.get());
+}
+
+#[test]
+fn test_cache_params() {
+    let count = Cell::new(0);
+    let mut cache = QueryCache::new(1 << 20, Duration::from_secs(60));
+
+    cache
+        .get_or_read("dummy", &["4,1"], &["binary"], || read("4,1", &count))
+        .unwrap();
+    cache
+        .get_or_read("dummy", &["4,1"], &["csv"], || read("4,1", &count))
+        .unwrap();
+    cache
+        .get_or_read("dummy", &["4,1"], &["binary"], || read("4,1", &count))
+        .unwrap();
+    assert_eq!(2, count.get());
+}
@@ -0,0 +1,86 @@
+use connectorx::{
+    destinations::arrow::ArrowDestination,
+    sources::{
+        connection_limit::{open_connections, set_max_connections},
+        postgres::{Binary, PostgresSource},
+    },
+    transports::PostgresArrowTransport,
+    Dispatcher,
+};
+use postgres::{Client, NoTls};
+use std::env;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+// the limit is global to the process, so this is the only test of this file
+#[test]
+fn test_max_connections() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let watcher_url = env::var("POSTGRES_URL").unwrap();
+    // tag the connections of the sources to count them on the server
+    let sep = if watcher_url.contains('?') { '&' } else { '?' };
+    let dburl = format!("{}{}application_name=cx_max_connections", watcher_url, sep);
+    set_max_connections(Some(2));
+
+    let done = Arc::new(AtomicBool::new(false));
+    let watcher = {
+        let done = done.clone();
+        thread::spawn(move || {
+            let mut client = Client::connect(&watcher_url, NoTls).unwrap();
+            let mut max_open = 0;
+            while !done.load(Ordering::SeqCst) {
+                let open: i64 = client
+                    .query_one(
+                        "select count(*) from pg_stat_activity where application_name = 'cx_max_connections'",
+                        &[],
+                    )
+                    .unwrap()
+                    .get(0);
+                max_open = max_open.max(open);
+            }
+            max_open
+        })
+    };
+
+    let reads: Vec<_> = (0..6)
+        .map(|_| {
+            let dburl = dburl.clone();
+            thread::spawn(move || {
+                let source = PostgresSource::<Binary>::new(&dburl, 1).unwrap();
+                let mut destination = ArrowDestination::new();
+                let dispatcher = Dispatcher::<_, _, PostgresArrowTransport>::new(
+                    source,
+                    &mut destination,
+                    &["select * from test_table"],
+                );
+                dispatcher.run().expect("run dispatcher");
+                let records = destination
+                    .finish(vec![
+                        "test_int".to_string(),
+                        "test_nullint".to_string(),
+                        "test_str".to_string(),
+                        "test_float".to_string(),
+                        "test_bool".to_string(),
+                    ])
+                    .unwrap();
+                records.iter().map(|rb| rb.num_rows()).sum::<usize>()
+            })
+        })
+        .collect();
+
+    for read in reads {
+        assert_eq!(6, read.join().unwrap());
+    }
+    done.store(true, Ordering::SeqCst);
+
+    let max_open = watcher.join().unwrap();
+    assert!(
+        0 < max_open && max_open <= 2,
+        "{} connections open",
+        max_open
+    );
+    // the connections are closed when returned to their pools
+    assert_eq!(0, open_connections());
+}
@@ -0,0 +1,33 @@
+use connectorx::{sources::checked_count, ConnectorAgentError};
+
+#[test]
+fn count_fits() {
+    assert_eq!(1314, checked_count(1314i64).unwrap());
+}
+
+#[test]
+fn count_negative() {
+    assert!(matches!(
+        checked_count(-1i64),
+        Err(ConnectorAgentError::CountOverflow(_))
+    ));
+}
+
+#[test]
+fn count_overflow() {
+    // a count one past what usize holds, the same situation as a 64-bit count on a 32-bit target
+    let count = usize::MAX as u128 + 1;
+    match checked_count(count) {
+        Err(ConnectorAgentError::CountOverflow(c)) => assert_eq!(count.to_string(), c),
+        r => panic!("expected CountOverflow, got {:?}", r),
+    }
+}
+
+#[cfg(target_pointer_width = "32")]
+#[test]
+fn count_overflow_32bit() {
+    assert!(matches!(
+        checked_count(u32::MAX as i64 + 1),
+        Err(ConnectorAgentError::CountOverflow(_))
+    ));
+}
@@ -0,0 +1,87 @@
+#![cfg(feature = "datafusion")]
+
+use arrow::array::{Float64Array, Int64Array, StringArray};
+use arrow::record_batch::RecordBatch;
+use connectorx::{
+    destinations::arrow::ArrowDestination, sources::datafusion::DataFusionSource,
+    transports::DataFusionArrowTransport, Dispatcher,
+};
+use datafusion::execution::context::{ExecutionConfig, ExecutionContext};
+use datafusion::prelude::CsvReadOptions;
+
+#[test]
+fn test_datafusion() {
+    let mut ctx = ExecutionContext::new();
+    ctx.register_csv("uspop", "./tests/data/uspop_0.csv", CsvReadOptions::new())
+        .expect("register csv");
+
+    let queries = ["SELECT * FROM uspop"];
+    let mut destination = ArrowDestination::new();
+    let dispatcher = Dispatcher::<_, _, DataFusionArrowTransport>::new(
+        DataFusionSource::new(ctx),
+        &mut destination,
+        &queries,
+    );
+    dispatcher.run().expect("run dispatcher");
+
+    let headers = ["Location", "State", "Zip", "Lat", "Long"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    let records: Vec<RecordBatch> = destination.finish(headers).unwrap();
+    assert_eq!(1, records.len());
+    assert_eq!(3, records[0].num_rows());
+
+    assert!(records[0]
+        .column(0)
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .unwrap()
+        .eq(&StringArray::from(vec!["Kenai", "Selma", "El Mirage"])));
+    assert!(records[0]
+        .column(2)
+        .as_any()
+        .downcast_ref::<Int64Array>()
+        .unwrap()
+        .eq(&Int64Array::from(vec![7610, 18980, 32308])));
+    assert_eq!(
+        60.5544444,
+        records[0]
+            .column(3)
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap()
+            .value(0)
+    );
+}
+
+#[test]
+fn test_datafusion_stream_batches() {
+    // one row per batch, read from as many partitions as the plan outputs
+    let config = ExecutionConfig::new()
+        .with_batch_size(1)
+        .with_concurrency(2);
+    let mut ctx = ExecutionContext::with_config(config);
+    ctx.register_csv("uspop", "./tests/data/uspop_0.csv", CsvReadOptions::new())
+        .expect("register csv");
+
+    let queries = ["SELECT Zip FROM uspop WHERE Zip > 10000"];
+    let mut destination = ArrowDestination::new();
+    let dispatcher = Dispatcher::<_, _, DataFusionArrowTransport>::new(
+        DataFusionSource::new(ctx),
+        &mut destination,
+        &queries,
+    );
+    dispatcher.run().expect("run dispatcher");
+
+    let records: Vec<RecordBatch> = destination.finish(vec!["Zip".to_string()]).unwrap();
+    let mut zips: Vec<i64> = records
+        .iter()
+        .flat_map(|rb| {
+            let col = rb.column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+            (0..col.len()).map(|i| col.value(i)).collect::<Vec<_>>()
+        })
+        .collect();
+    zips.sort();
+    assert_eq!(vec![18980, 32308], zips);
+}
@@ -0,0 +1,43 @@
+use chrono::NaiveDate;
+use connectorx::range::Range;
+
+#[test]
+fn parse_bounded() {
+    let range: Range<i32> = "[1,10)".parse().unwrap();
+    assert_eq!(Range::new(Some(1), Some(10), true, false), range);
+}
+
+#[test]
+fn parse_unbounded() {
+    let range: Range<i32> = "[1,)".parse().unwrap();
+    assert_eq!(Range::new(Some(1), None, true, false), range);
+
+    let range: Range<i32> = "(,)".parse().unwrap();
+    assert_eq!(Range::new(None, None, false, false), range);
+}
+
+#[test]
+fn parse_empty() {
+    let range: Range<NaiveDate> = "empty".parse().unwrap();
+    assert!(range.empty);
+}
+
+#[test]
+fn parse_date() {
+    let range: Range<NaiveDate> = "[2021-01-01,\"2021-02-01\"]".parse().unwrap();
+    assert_eq!(
+        Range::new(
+            Some(NaiveDate::from_ymd(2021, 1, 1)),
+            Some(NaiveDate::from_ymd(2021, 2, 1)),
+            true,
+            true
+        ),
+        range
+    );
+}
+
+#[test]
+fn parse_invalid() {
+    assert!("1,10".parse::<Range<i32>>().is_err());
+    assert!("[a,10)".parse::<Range<i32>>().is_err());
+}
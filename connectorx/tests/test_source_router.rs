@@ -0,0 +1,39 @@
+use connectorx::source_router::partition_ranges;
+
+#[test]
+fn partition_ranges_even() {
+    assert_eq!(
+        vec![(0, 2), (2, 4), (4, 6)],
+        partition_ranges(0, 5, 3).unwrap()
+    );
+}
+
+#[test]
+fn partition_ranges_remainder() {
+    // the last partition takes what does not divide evenly
+    assert_eq!(
+        vec![(0, 3), (3, 6), (6, 11)],
+        partition_ranges(0, 10, 3).unwrap()
+    );
+}
+
+#[test]
+fn partition_ranges_invalid() {
+    assert!(partition_ranges(0, 10, 0).is_err());
+    assert!(partition_ranges(10, 0, 2).is_err());
+}
+
+#[test]
+fn partition_ranges_extremes() {
+    // the size of the range does not fit in an i64
+    assert_eq!(
+        vec![(i64::MIN, -1), (-1, i64::MAX)],
+        partition_ranges(i64::MIN, i64::MAX - 1, 2).unwrap()
+    );
+    assert_eq!(
+        vec![(i64::MAX - 1, i64::MAX)],
+        partition_ranges(i64::MAX - 1, i64::MAX - 1, 1).unwrap()
+    );
+    // the exclusive upper bound of i64::MAX does not fit in an i64
+    assert!(partition_ranges(0, i64::MAX, 2).is_err());
+}
@@ -0,0 +1,62 @@
+use connectorx::{
+    sql::{hash_partition_query, offset_partition_query, watermark_query},
+    ConnectorAgentError,
+};
+use sqlparser::dialect::PostgreSqlDialect;
+
+#[test]
+fn offset_partition_without_order_by() {
+    let res = offset_partition_query("SELECT * FROM test_table", 10, 5, &PostgreSqlDialect {});
+    assert!(matches!(
+        res,
+        Err(ConnectorAgentError::SQLQueryNotOrdered(_))
+    ));
+}
+
+#[test]
+fn offset_partition_with_order_by() {
+    let query = offset_partition_query(
+        "SELECT * FROM test_table ORDER BY test_int",
+        10,
+        5,
+        &PostgreSqlDialect {},
+    )
+    .unwrap();
+    assert_eq!(
+        "SELECT * FROM test_table ORDER BY test_int LIMIT 5 OFFSET 10",
+        query
+    );
+}
+
+#[test]
+fn watermark_bounds() {
+    let query = watermark_query(
+        "SELECT * FROM test_table",
+        "test_int",
+        Some("2"),
+        Some("1314"),
+        &PostgreSqlDialect {},
+    )
+    .unwrap();
+    assert_eq!(
+        "SELECT * FROM (SELECT * FROM test_table) AS CXTMPTAB_WATERMARK WHERE CXTMPTAB_WATERMARK.test_int > '2' AND CXTMPTAB_WATERMARK.test_int <= '1314'",
+        query
+    );
+}
+
+#[test]
+fn hash_partition() {
+    let query = hash_partition_query(
+        "SELECT * FROM test_table",
+        "test_str",
+        "hashtext",
+        3,
+        1,
+        &PostgreSqlDialect {},
+    )
+    .unwrap();
+    assert_eq!(
+        "SELECT * FROM (SELECT * FROM test_table) AS CXTMPTAB_PART WHERE abs(MOD(hashtext(CAST(CXTMPTAB_PART.test_str AS TEXT)), 3)) = 1",
+        query
+    );
+}
@@ -0,0 +1,229 @@
+use connectorx::{
+    dispatcher::retry_on_schema_change,
+    source_router::SourceType,
+    sources::{
+        sqlite::{SqliteSource, SqliteTypeSystem},
+        PartitionParser, Produce, Source, SourcePartition,
+    },
+    ConnectorAgentError,
+};
+use rusqlite::Connection;
+use serde_json::{json, Value};
+use std::env;
+
+fn create_json_db(name: &str) -> String {
+    let path = env::temp_dir().join(name);
+    let _ = std::fs::remove_file(&path);
+    let conn = Connection::open(&path).unwrap();
+    conn.execute_batch(
+        "CREATE TABLE test_json(id INTEGER NOT NULL, doc JSON);
+         INSERT INTO test_json VALUES (0, '{\"product\": \"Beer\", \"qty\": 6}');
+         INSERT INTO test_json VALUES (1, NULL);
+         INSERT INTO test_json VALUES (2, '[1, 2]');",
+    )
+    .unwrap();
+    path.to_str().unwrap().to_string()
+}
+
+#[test]
+fn test_sqlite_json() {
+    let path = create_json_db("connectorx_test_sqlite_json.db");
+    let mut source = SqliteSource::new(&path, 1).unwrap();
+    source.set_queries(&["SELECT doc FROM test_json ORDER BY id"]);
+    source.fetch_metadata().unwrap();
+    assert!(matches!(source.schema()[0], SqliteTypeSystem::JSON(true)));
+
+    let mut partitions = source.partition().unwrap();
+    let mut partition = partitions.remove(0);
+    partition.prepare().expect("run query");
+    assert_eq!(3, partition.nrows());
+
+    let mut parser = partition.parser().unwrap();
+    let mut docs: Vec<Option<Value>> = vec![];
+    for _ in 0..3 {
+        docs.push(parser.produce().unwrap());
+    }
+    assert_eq!(
+        vec![
+            Some(json!({"product": "Beer", "qty": 6})),
+            None,
+            Some(json!([1, 2]))
+        ],
+        docs
+    );
+}
+
+#[test]
+fn test_sqlite_json_as_text() {
+    let path = create_json_db("connectorx_test_sqlite_json_text.db");
+    let mut source = SqliteSource::new(&path, 1).unwrap();
+    source.parse_json(false);
+    source.set_queries(&["SELECT doc FROM test_json ORDER BY id"]);
+    source.fetch_metadata().unwrap();
+    assert!(matches!(source.schema()[0], SqliteTypeSystem::Text(true)));
+}
+
+#[test]
+fn test_sqlite_parser_finished() {
+    let path = create_json_db("connectorx_test_sqlite_finished.db");
+    let mut source = SqliteSource::new(&path, 1).unwrap();
+    source.set_queries(&["SELECT id FROM test_json ORDER BY id"]);
+    source.fetch_metadata().unwrap();
+
+    let mut partitions = source.partition().unwrap();
+    let mut partition = partitions.remove(0);
+    partition.prepare().expect("run query");
+    let nrows = partition.nrows();
+    assert_eq!(3, nrows);
+
+    let mut parser = partition.parser().unwrap();
+    let mut ids: Vec<i64> = vec![];
+    for _ in 0..nrows {
+        assert!(!parser.is_finished().unwrap());
+        ids.push(parser.produce().unwrap());
+    }
+    assert_eq!(vec![0, 1, 2], ids);
+    assert!(parser.is_finished().unwrap());
+    // checking again does not step over anything
+    assert!(parser.is_finished().unwrap());
+}
+
+#[test]
+fn test_sqlite_produce_mismatch() {
+    let path = env::temp_dir().join("connectorx_test_sqlite_mismatch.db");
+    let _ = std::fs::remove_file(&path);
+    let conn = Connection::open(&path).unwrap();
+    // the declared type is only an affinity, the cell keeps the REAL value
+    conn.execute_batch(
+        "CREATE TABLE test_mismatch(id INTEGER NOT NULL, val INTEGER);
+         INSERT INTO test_mismatch VALUES (0, 1.5);",
+    )
+    .unwrap();
+
+    let mut source = SqliteSource::new(path.to_str().unwrap(), 1).unwrap();
+    source.set_queries(&["SELECT val FROM test_mismatch"]);
+    source.fetch_metadata().unwrap();
+
+    let mut partitions = source.partition().unwrap();
+    let mut partition = partitions.remove(0);
+    partition.prepare().expect("run query");
+
+    let mut parser = partition.parser().unwrap();
+    let res: Result<Option<i64>, _> = parser.produce();
+    match res {
+        Err(e @ ConnectorAgentError::CannotProduce(..)) => {
+            assert!(e.to_string().contains("holds a REAL value"), "{}", e)
+        }
+        r => panic!("expected CannotProduce, got {:?}", r),
+    }
+}
+
+#[cfg(feature = "csvtab")]
+#[test]
+fn test_sqlite_csv_virtual_table() {
+    let path = env::temp_dir().join("connectorx_test_sqlite_csvtab.db");
+    let _ = std::fs::remove_file(&path);
+    let conn = Connection::open(&path).unwrap();
+    rusqlite::vtab::csvtab::load_module(&conn).unwrap();
+    conn.execute_batch(&format!(
+        "CREATE VIRTUAL TABLE uspop USING csv(filename='{}/tests/data/uspop_0.csv', header=yes)",
+        env!("CARGO_MANIFEST_DIR")
+    ))
+    .unwrap();
+
+    let mut source = SqliteSource::new(path.to_str().unwrap(), 1).unwrap();
+    source.set_queries(&["SELECT Location, State FROM uspop WHERE Zip = '7610'"]);
+    source.fetch_metadata().unwrap();
+    assert_eq!(vec!["Location", "State"], source.names());
+
+    let mut partitions = source.partition().unwrap();
+    let mut partition = partitions.remove(0);
+    partition.prepare().expect("run query");
+    assert_eq!(1, partition.nrows());
+
+    let mut parser = partition.parser().unwrap();
+    let location: Box<str> = parser.produce().unwrap();
+    let state: Box<str> = parser.produce().unwrap();
+    assert_eq!(("Kenai", "AK"), (&*location, &*state));
+}
+
+#[test]
+fn test_sqlite_retry_on_schema_change() {
+    let path = env::temp_dir().join("connectorx_test_sqlite_retry.db");
+    let _ = std::fs::remove_file(&path);
+    let conn = Connection::open(&path).unwrap();
+    conn.execute_batch(
+        "CREATE TABLE test_migrate(val INTEGER);
+         INSERT INTO test_migrate VALUES (1);",
+    )
+    .unwrap();
+
+    let mut attempts = 0;
+    let vals = retry_on_schema_change(1, || {
+        attempts += 1;
+        let mut source = SqliteSource::new(path.to_str().unwrap(), 1)?;
+        source.set_queries(&["SELECT * FROM test_migrate"]);
+        source.fetch_metadata()?;
+        let schema = source.schema();
+        if attempts == 1 {
+            // a migration adds a column between fetching the metadata and reading
+            conn.execute_batch(
+                "DROP TABLE test_migrate;
+                 CREATE TABLE test_migrate(val TEXT, note TEXT);
+                 INSERT INTO test_migrate VALUES ('one', 'migrated');",
+            )
+            .unwrap();
+        }
+
+        let mut partitions = source.partition()?;
+        let mut partition = partitions.remove(0);
+        partition.prepare()?;
+        let mut parser = partition.parser()?;
+        let val = match schema[0] {
+            SqliteTypeSystem::Int8(_) => {
+                let v: Option<i64> = parser.produce()?;
+                v.map(|v| v.to_string())
+            }
+            _ => {
+                let v: Option<Box<str>> = parser.produce()?;
+                v.map(String::from)
+            }
+        };
+        Ok(vec![val])
+    })
+    .unwrap();
+
+    assert_eq!(2, attempts);
+    assert_eq!(vec![Some("one".to_string())], vals);
+}
+
+#[test]
+fn test_sqlite_offset_part_queries() {
+    let path = env::temp_dir().join("connectorx_test_sqlite_offset.db");
+    let _ = std::fs::remove_file(&path);
+    let conn = Connection::open(&path).unwrap();
+    conn.execute_batch(
+        "CREATE TABLE test_offset(id INTEGER NOT NULL);
+         INSERT INTO test_offset VALUES (4), (0), (3), (1), (2);",
+    )
+    .unwrap();
+    let url = format!("sqlite://{}", path.to_str().unwrap());
+
+    let queries = SourceType::Sqlite
+        .get_offset_part_queries(&url, "SELECT id FROM test_offset ORDER BY id", 2)
+        .unwrap();
+    let ids: Vec<Vec<i64>> = queries
+        .iter()
+        .map(|query| {
+            let mut stmt = conn.prepare(query).unwrap();
+            let rows = stmt.query_map([], |row| row.get(0)).unwrap();
+            rows.map(|id| id.unwrap()).collect()
+        })
+        .collect();
+    assert_eq!(vec![vec![0, 1], vec![2, 3, 4]], ids);
+
+    assert!(matches!(
+        SourceType::Sqlite.get_offset_part_queries(&url, "SELECT id FROM test_offset", 2),
+        Err(ConnectorAgentError::SQLQueryNotOrdered(_))
+    ));
+}